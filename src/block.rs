@@ -3,26 +3,64 @@ use std::time::Duration;
 
 use crate::day::Day;
 use crate::infer::infer_block;
+use crate::infer::try_infer_block;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Block {
     pub from: DateTime<Local>,
     pub to: DateTime<Local>,
+    pub project: Option<String>,
+    pub note: Option<String>,
 }
 
 impl Block {
-    pub fn parse(block: &str, day: &Day) -> Self {
-        infer_block(block, day)
+    pub fn new(from: DateTime<Local>, to: DateTime<Local>) -> Self {
+        Self {
+            from,
+            to,
+            project: None,
+            note: None,
+        }
+    }
+
+    /// Infers a block from a CLI shorthand relative to `now`.
+    pub fn parse(block: &str, day: &Day, now: &DateTime<Local>) -> Self {
+        infer_block(block, day, now)
+    }
+
+    pub fn try_parse(block: &str, day: &Day, now: &DateTime<Local>) -> Result<Self, String> {
+        try_infer_block(block, day, now)
     }
 
     pub fn duration(&self) -> Duration {
-        (self.to - self.from).to_std().unwrap()
+        self.try_duration().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fails for blocks that end before they start.
+    pub fn try_duration(&self) -> Result<Duration, String> {
+        (self.to - self.from)
+            .to_std()
+            .map_err(|_| format!("Block ends at {} before it starts", self.to))
+    }
+
+    /// Like `duration`, but an ongoing block started earlier on the same day
+    /// counts up to `now`.
+    pub fn live_duration(&self, now: &DateTime<Local>) -> Duration {
+        if self.is_running(now) {
+            (*now - self.from).to_std().unwrap()
+        } else {
+            self.duration()
+        }
     }
 
     pub fn is_ongoing(&self) -> bool {
         self.from == self.to
     }
 
+    pub fn is_running(&self, now: &DateTime<Local>) -> bool {
+        self.is_ongoing() && self.from.date() == now.date() && self.from < *now
+    }
+
     pub fn contains(&self, other: &Self) -> bool {
         self.from <= other.from && self.to >= other.to
     }
@@ -44,7 +82,7 @@ impl Ord for Block {
 
 impl PartialOrd for Block {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.from.cmp(&other.from))
+        Some(self.cmp(other))
     }
 }
 
@@ -56,47 +94,60 @@ mod tests {
     fn test_not_ongoing() {
         let from = Local.ymd(2022, 2, 12).and_hms(8, 0, 0);
         let to = Local.ymd(2022, 2, 12).and_hms(9, 0, 0);
-        let block = Block { from, to };
+        let block = Block::new(from, to);
 
-        assert_eq!(block.is_ongoing(), false);
+        assert!(!block.is_ongoing());
     }
 
     #[test]
     fn test_ongoing() {
         let date = Local.ymd(2022, 2, 12).and_hms(8, 0, 0);
-        let block = Block {
-            from: date,
-            to: date,
-        };
+        let block = Block::new(date, date);
 
-        assert_eq!(block.is_ongoing(), true);
+        assert!(block.is_ongoing());
     }
 
     #[test]
     fn test_contains() {
         let inner_from = Local.ymd(2022, 2, 12).and_hms(8, 0, 0);
         let inner_to = Local.ymd(2022, 2, 12).and_hms(9, 0, 0);
-        let inner_block = Block {
-            from: inner_from,
-            to: inner_to,
-        };
+        let inner_block = Block::new(inner_from, inner_to);
 
         let outer_from = Local.ymd(2022, 2, 12).and_hms(8, 0, 0);
         let outer_to = Local.ymd(2022, 2, 12).and_hms(10, 0, 0);
-        let outer_block = Block {
-            from: outer_from,
-            to: outer_to,
-        };
+        let outer_block = Block::new(outer_from, outer_to);
 
-        assert_eq!(true, outer_block.contains(&inner_block));
-        assert_eq!(false, inner_block.contains(&outer_block));
+        assert!(outer_block.contains(&inner_block));
+        assert!(!inner_block.contains(&outer_block));
     }
 
     #[test]
     fn test_block_inferrence() {
         let today = NaiveDate::from_ymd(2022, 2, 12);
         let day = Day::from_date(&today);
-        let block = Block::parse("08:00", &day);
-        assert_eq!(true, block.is_ongoing());
+        let block = Block::parse("08:00", &day, &Local::now());
+        assert!(block.is_ongoing());
+        assert!(Block::try_parse("8:00-x", &day, &Local::now()).is_err());
+    }
+
+    #[test]
+    fn test_backwards_duration() {
+        let from = Local.ymd(2022, 2, 12).and_hms(9, 0, 0);
+        let to = Local.ymd(2022, 2, 12).and_hms(8, 0, 0);
+        assert!(Block::new(from, to).try_duration().is_err());
+    }
+
+    #[test]
+    fn test_live_duration() {
+        let from = Local.ymd(2022, 2, 12).and_hms(8, 0, 0);
+        let block = Block::new(from, from);
+
+        let now = Local.ymd(2022, 2, 12).and_hms(9, 30, 0);
+        assert!(block.is_running(&now));
+        assert_eq!(Duration::from_secs(90 * 60), block.live_duration(&now));
+
+        let tomorrow = Local.ymd(2022, 2, 13).and_hms(9, 30, 0);
+        assert!(!block.is_running(&tomorrow));
+        assert_eq!(Duration::from_secs(0), block.live_duration(&tomorrow));
     }
 }
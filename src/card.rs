@@ -0,0 +1,152 @@
+use chrono::prelude::*;
+use std::collections::HashSet;
+use std::env::var;
+use std::path::Path;
+
+use crate::compliance::Violation;
+use crate::config::Config;
+use crate::fmt::Precision;
+use crate::infer;
+use crate::locale::Locale;
+use crate::store::StoreError;
+use crate::theme::Theme;
+
+pub const DEFAULT_HOURS_DIR: &str = "./hours";
+
+/// An hours directory with its config, the selected card and the dates
+/// touched by the current command.
+pub struct PunchCard {
+    hours_dir_path: String,
+    card: Option<String>,
+    pub config: Config,
+    pub theme: Theme,
+    pub locale: Locale,
+    holidays: HashSet<NaiveDate>,
+    selected_dates: HashSet<NaiveDate>,
+    modified_dates: HashSet<NaiveDate>,
+    violations: Vec<Violation>,
+}
+
+impl Default for PunchCard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PunchCard {
+    /// Opens the hours directory given by `PUNCH_HOURS_DIR`, panicking on an
+    /// invalid config.
+    pub fn new() -> PunchCard {
+        let hours_dir_path = match var("PUNCH_HOURS_DIR") {
+            Ok(dir) => dir,
+            Err(_) => DEFAULT_HOURS_DIR.to_string(),
+        };
+
+        PunchCard::open(&hours_dir_path).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Opens an hours directory, checking its config up front so the
+    /// config getters don't panic later on.
+    pub fn open(hours_dir_path: &str) -> Result<PunchCard, StoreError> {
+        Self::with_config(hours_dir_path, Config::load(hours_dir_path))
+    }
+
+    /// Opens an hours directory with the given config instead of the one
+    /// found through `PUNCH_CONFIG` or in the directory.
+    pub fn with_config(hours_dir_path: &str, config: Config) -> Result<PunchCard, StoreError> {
+        config.validate().map_err(StoreError::Config)?;
+        let theme = Theme::try_from_config(&config).map_err(StoreError::Config)?;
+        let locale = Locale::try_from_config(&config).map_err(StoreError::Config)?;
+        let holidays = config
+            .try_holidays()
+            .map_err(StoreError::Config)?
+            .into_iter()
+            .collect();
+
+        Ok(PunchCard {
+            hours_dir_path: hours_dir_path.to_string(),
+            card: None,
+            config,
+            theme,
+            locale,
+            holidays,
+            selected_dates: HashSet::new(),
+            modified_dates: HashSet::new(),
+            violations: vec![],
+        })
+    }
+
+    pub fn hours_dir_path(&self) -> &str {
+        &self.hours_dir_path
+    }
+
+    /// Switches to a named card, i.e. a separate set of BRF files in a
+    /// subdirectory of the hours directory.
+    pub fn select_card(&mut self, card: &str) {
+        self.try_select_card(card)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_select_card(&mut self, card: &str) -> Result<(), StoreError> {
+        let known = self.config.cards().iter().any(|c| c == card)
+            || Path::new(&self.hours_dir_path).join(card).is_dir();
+
+        if !known {
+            return Err(StoreError::UnknownCard(card.to_string()));
+        }
+
+        self.card = Some(card.to_string());
+        Ok(())
+    }
+
+    pub fn card_dir_path(&self) -> String {
+        match &self.card {
+            Some(card) => format!("{}/{}", self.hours_dir_path, card),
+            None => self.hours_dir_path.clone(),
+        }
+    }
+
+    pub fn brf_file_path(&self, year: i32, month: u32) -> String {
+        format!("{}/{}-{}.txt", self.card_dir_path(), year, month)
+    }
+
+    pub fn select_date(&mut self, date: &NaiveDate) {
+        self.selected_dates.insert(*date);
+    }
+
+    pub fn modify_date(&mut self, date: &NaiveDate) {
+        self.modified_dates.insert(*date);
+    }
+
+    pub fn is_holiday(&self, date: &NaiveDate) -> bool {
+        self.holidays.contains(date)
+    }
+
+    pub fn holidays(&self) -> &HashSet<NaiveDate> {
+        &self.holidays
+    }
+
+    pub fn was_selected(&self, date: &NaiveDate) -> bool {
+        self.selected_dates.contains(date)
+    }
+
+    pub fn was_modified(&self, date: &NaiveDate) -> bool {
+        self.modified_dates.contains(date)
+    }
+
+    /// The current time at the configured precision.
+    pub fn now(&self) -> DateTime<Local> {
+        match self.config.precision() {
+            Precision::Minutes => infer::now_minute(),
+            Precision::Seconds => Local::now().with_nanosecond(0).unwrap(),
+        }
+    }
+
+    pub fn flag_violations(&mut self, violations: Vec<Violation>) {
+        self.violations = violations;
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+}
@@ -0,0 +1,371 @@
+use chrono::prelude::*;
+
+use crate::block::Block;
+use crate::card::PunchCard;
+use crate::day::Day;
+use crate::fmt;
+use crate::fmt::OutputMode;
+use crate::locale::Locale;
+use crate::month::Month;
+use crate::theme::Rgb;
+
+pub const DEFAULT_WIDTH: usize = 80;
+/// The timeline spans at least these hours, extended to fit all blocks.
+const TIMELINE_START: u32 = 6;
+const TIMELINE_END: u32 = 20;
+/// Minutes per timeline cell, the smallest one that fits is used.
+const TIMELINE_STEPS: [u32; 6] = [5, 10, 15, 20, 30, 60];
+const TIMELINE_FILLED: char = '█';
+const TIMELINE_EMPTY: char = '·';
+/// Bar chart cells, from an eighth to a full cell.
+const BAR_EIGHTHS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+const MIN_CELLS: usize = 10;
+const TOTAL_WIDTH: usize = 5;
+const CALENDAR_CELL_WIDTH: usize = 8;
+const CALENDAR_EMPTY_TOTAL: &str = "-";
+/// The green and yellow of xterm, so they stay plain ANSI colours on
+/// terminals without true colour.
+const CALENDAR_REACHED: Rgb = Rgb(0, 205, 0);
+const CALENDAR_MISSED: Rgb = Rgb(205, 205, 0);
+
+/// Alternative terminal views of a month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chart {
+    /// One line per day, blocks filled in on an hour axis
+    Timeline,
+    /// One bar per day, proportional to the total
+    Bars,
+}
+
+/// Formats the chart to fit the width, usually that of the terminal.
+pub fn format_chart(month: &Month, card: &PunchCard, chart: Chart, width: usize) -> String {
+    let now = card.now();
+
+    match chart {
+        Chart::Timeline => format_timeline(month, card, width, &now),
+        Chart::Bars => format_bars(month, card, width, &now),
+    }
+}
+
+pub fn format_timeline(
+    month: &Month,
+    card: &PunchCard,
+    width: usize,
+    now: &DateTime<Local>,
+) -> String {
+    let days = month.full_sorted_days();
+    let (start, end) = timeline_hours(&days, now);
+    let minutes = (end - start) * 60;
+    let available = cells(width, &card.locale) as u32;
+    let step = *TIMELINE_STEPS
+        .iter()
+        .find(|s| minutes / *s <= available)
+        .unwrap_or(TIMELINE_STEPS.last().unwrap());
+
+    let mut axis = vec![' '; (minutes / step) as usize + 2];
+    let mut free = 0;
+    for hour in start..=end {
+        let position = ((hour - start) * 60 / step) as usize;
+        if position >= free && position + 2 <= axis.len() {
+            axis.splice(position..position + 2, format!("{:02}", hour).chars());
+            free = position + 3;
+        }
+    }
+    let axis = format!(
+        "{}{}{}",
+        " ".repeat(date_width(&card.locale)),
+        fmt::SPACER,
+        axis.iter().collect::<String>().trim_end()
+    );
+
+    let lines = days
+        .iter()
+        .enumerate()
+        .map(|(index, day)| {
+            let cells = (0..minutes / step)
+                .map(|cell| {
+                    let from = ((start * 60 + cell * step) * 60) as i64;
+                    let to = from + (step * 60) as i64;
+                    let covered = day
+                        .blocks
+                        .iter()
+                        .filter_map(|b| block_seconds(b, &day.date, now))
+                        .map(|(f, t)| (t.min(to) - f.max(from)).max(0))
+                        .sum::<i64>();
+
+                    if covered * 2 >= to - from {
+                        TIMELINE_FILLED
+                    } else {
+                        TIMELINE_EMPTY
+                    }
+                })
+                .collect::<String>();
+
+            format_chart_line(day, index, &cells, card, now)
+        })
+        .collect::<Vec<_>>();
+
+    format_chart_month(month, card, &axis, lines, now)
+}
+
+pub fn format_bars(month: &Month, card: &PunchCard, width: usize, now: &DateTime<Local>) -> String {
+    let days = month.full_sorted_days();
+    let available = cells(width, &card.locale) as u64;
+    let max = days
+        .iter()
+        .map(|d| d.live_duration(now).as_secs())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let lines = days
+        .iter()
+        .enumerate()
+        .map(|(index, day)| {
+            let eighths = (day.live_duration(now).as_secs() * available * 8 + max / 2) / max;
+            let mut bar = BAR_EIGHTHS[7].to_string().repeat((eighths / 8) as usize);
+            if let Some(partial) = (eighths % 8).checked_sub(1) {
+                bar.push(BAR_EIGHTHS[partial as usize]);
+            }
+            let bar = format!("{:width$}", bar, width = available as usize);
+
+            format_chart_line(day, index, &bar, card, now)
+        })
+        .collect::<Vec<_>>();
+
+    format_chart_month(month, card, "", lines, now)
+}
+
+/// Prints the month as a calendar grid with weeks starting on Monday. Day
+/// totals that reach the daily target are green, other totals yellow.
+pub fn format_calendar(month: &Month, card: &PunchCard, now: &DateTime<Local>) -> String {
+    let days = month.full_sorted_days();
+    let target = card.config.daily_target();
+
+    let mut weekday = Weekday::Mon;
+    let mut header = vec![];
+    for _ in 0..7 {
+        header.push(format!(
+            "{:<width$}",
+            card.locale.weekday(weekday),
+            width = CALENDAR_CELL_WIDTH
+        ));
+        weekday = weekday.succ();
+    }
+
+    let offset = days
+        .first()
+        .map_or(0, |d| d.date.weekday().num_days_from_monday() as usize);
+    let mut cells = vec![" ".repeat(CALENDAR_CELL_WIDTH); offset];
+    cells.extend(days.iter().map(|day| {
+        let duration = day.live_duration(now);
+        let total = match day.blocks.is_empty() {
+            true => CALENDAR_EMPTY_TOTAL.to_string(),
+            false => fmt::format_term_duration(&duration, card),
+        };
+        let cell = format!(
+            "{:<width$}",
+            format!("{:02} {:>5}", day.date.day(), total),
+            width = CALENDAR_CELL_WIDTH
+        );
+
+        if card.was_modified(&day.date) || card.was_selected(&day.date) || day.blocks.is_empty() {
+            fmt::highlight(cell, day, card, now)
+        } else if duration >= target {
+            card.theme.paint(cell, Some(CALENDAR_REACHED))
+        } else {
+            card.theme.paint(cell, Some(CALENDAR_MISSED))
+        }
+    }));
+
+    let weeks = cells
+        .chunks(7)
+        .map(|week| week.join(fmt::SPACER).trim_end().to_string())
+        .collect::<Vec<_>>();
+
+    format!(
+        "{}{}{}{}{}{}Total: {}",
+        card.locale.month_title(month),
+        fmt::NEWLINE.repeat(2),
+        header.join(fmt::SPACER).trim_end(),
+        fmt::NEWLINE,
+        weeks.join(fmt::NEWLINE),
+        fmt::NEWLINE.repeat(2),
+        fmt::format_term_duration(&month.live_duration(now), card)
+    )
+}
+
+fn format_chart_line(
+    day: &Day,
+    index: usize,
+    chart: &str,
+    card: &PunchCard,
+    now: &DateTime<Local>,
+) -> String {
+    let line = format!(
+        "{}{}{}{}{}",
+        fmt::format_date(&day.date, OutputMode::Term, index, &card.locale),
+        fmt::SPACER,
+        chart,
+        fmt::SPACER,
+        fmt::format_term_duration(&day.live_duration(now), card)
+    );
+
+    fmt::highlight(line, day, card, now)
+}
+
+fn format_chart_month(
+    month: &Month,
+    card: &PunchCard,
+    header: &str,
+    lines: Vec<String>,
+    now: &DateTime<Local>,
+) -> String {
+    let header = match header {
+        "" => "".to_string(),
+        h => format!("{}{}", h, fmt::NEWLINE),
+    };
+
+    format!(
+        "{}{}{}{}{}Total: {}",
+        card.locale.month_title(month),
+        fmt::NEWLINE.repeat(2),
+        header,
+        lines.join(fmt::NEWLINE),
+        fmt::NEWLINE.repeat(2),
+        fmt::format_term_duration(&month.live_duration(now), card)
+    )
+}
+
+fn date_width(locale: &Locale) -> usize {
+    locale
+        .format_date(&NaiveDate::from_ymd(2022, 1, 1))
+        .chars()
+        .count()
+}
+
+/// Cells left for the chart between the date and the total.
+fn cells(width: usize, locale: &Locale) -> usize {
+    width
+        .saturating_sub(date_width(locale) + 2 * fmt::SPACER.len() + TOTAL_WIDTH)
+        .max(MIN_CELLS)
+}
+
+/// Start and end of a block in seconds since midnight of the date. Running
+/// blocks end now, other ongoing blocks are skipped.
+fn block_seconds(block: &Block, date: &NaiveDate, now: &DateTime<Local>) -> Option<(i64, i64)> {
+    let to = if block.is_running(now) {
+        *now
+    } else if block.is_ongoing() {
+        return None;
+    } else {
+        block.to
+    };
+    let midnight = date.and_hms(0, 0, 0);
+
+    Some((
+        (block.from.naive_local() - midnight).num_seconds(),
+        (to.naive_local() - midnight).num_seconds(),
+    ))
+}
+
+/// The default hours, extended to full hours around all blocks.
+fn timeline_hours(days: &[Day], now: &DateTime<Local>) -> (u32, u32) {
+    let seconds = days
+        .iter()
+        .flat_map(|d| {
+            d.blocks
+                .iter()
+                .filter_map(|b| block_seconds(b, &d.date, now))
+        })
+        .collect::<Vec<_>>();
+
+    let first = seconds.iter().map(|(from, _)| *from / 3600).min();
+    let last = seconds.iter().map(|(_, to)| (*to + 3599) / 3600).max();
+
+    (
+        first.map_or(TIMELINE_START, |f| TIMELINE_START.min(f.max(0) as u32)),
+        last.map_or(TIMELINE_END, |l| TIMELINE_END.max(l.min(24) as u32)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::theme::Palette;
+
+    fn card() -> PunchCard {
+        let dir = std::env::temp_dir().join(format!("punch-chart-{}", std::process::id()));
+        PunchCard::with_config(dir.to_str().unwrap(), Config::default()).unwrap()
+    }
+
+    fn month() -> Month {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-12:00   13:00-17:00   Total: 08:00
+            02.02.22   05:30-09:00   Total: 03:30
+
+            Total: 11:30
+        "#;
+
+        Month::from_brf(contents, 2022, 2)
+    }
+
+    fn now() -> DateTime<Local> {
+        Local.ymd(2022, 3, 1).and_hms(12, 0, 0)
+    }
+
+    #[test]
+    fn test_timeline_hours() {
+        let days = month().sorted_days();
+        assert_eq!((5, 20), timeline_hours(&days, &now()));
+        assert_eq!((6, 20), timeline_hours(&days[..1], &now()));
+        assert_eq!((6, 20), timeline_hours(&[], &now()));
+    }
+
+    #[test]
+    fn test_format_timeline() {
+        let card = card();
+        let timeline = format_timeline(&month(), &card, 40, &now());
+        let lines = timeline.lines().collect::<Vec<_>>();
+
+        // 15 hours in 60 minute cells fit into the 15 cells of 40 columns,
+        // labels are skipped where they would touch
+        assert_eq!("                 05 08 11 14 17 20", lines[2]);
+        assert_eq!("Tue   01.02.22   ···████·████···   08:00", lines[3]);
+        assert_eq!("Wed   02.02.22   ████···········   03:30", lines[4]);
+        assert_eq!("Total: 11:30", *lines.last().unwrap());
+    }
+
+    #[test]
+    fn test_format_calendar() {
+        let mut card = card();
+        card.theme.palette = Palette::Plain;
+        let calendar = format_calendar(&month(), &card, &now());
+        let lines = calendar.lines().collect::<Vec<_>>();
+
+        assert_eq!(
+            "Mon        Tue        Wed        Thu        Fri        Sat        Sun",
+            lines[2]
+        );
+        assert_eq!(
+            "           01 08:00   02 03:30   03     -   04     -   05     -   06     -",
+            lines[3]
+        );
+        assert_eq!("28     -", lines[7]);
+        assert_eq!("Total: 11:30", lines[9]);
+    }
+
+    #[test]
+    fn test_format_bars() {
+        let card = card();
+        let bars = format_bars(&month(), &card, 41, &now());
+        let lines = bars.lines().collect::<Vec<_>>();
+
+        assert_eq!("Tue   01.02.22   ████████████████   08:00", lines[2]);
+        assert_eq!("Wed   02.02.22   ███████            03:30", lines[3]);
+        assert_eq!("Thu   03.02.22                      00:00", lines[4]);
+    }
+}
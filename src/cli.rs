@@ -1,56 +1,425 @@
+use clap::ArgEnum;
+use clap::ArgGroup;
+use clap::Args as ClapArgs;
+use clap::CommandFactory;
+use clap::ErrorKind;
 use clap::Parser;
+use clap::Subcommand;
+use punch::chart::Chart;
 
+use crate::completions::Candidates;
+
+/// `punch 8-12` is a shorthand for `punch add 8-12`, `punch` on its own for
+/// `punch show`. Blocks may start with `-`, like `-15m`, as long as they
+/// don't look like a flag.
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, allow_hyphen_values = true)]
 pub struct Args {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
+    /// Blocks to add to the selected day
     pub blocks: Vec<String>,
 
-    #[clap(long)]
+    /// Add the blocks of a schedule template from the config
+    #[clap(short, long)]
+    pub template: Option<String>,
+
+    #[clap(flatten)]
+    pub selection: Selection,
+
+    /// Use the BRF files of a named card (hours subdirectory)
+    #[clap(long, global = true)]
+    pub card: Option<String>,
+
+    /// Simulate the changes and don't write them to the BRF file
+    #[clap(long, global = true)]
+    pub dry_run: bool,
+
+    /// When to colour the output
+    #[clap(long, arg_enum, global = true, default_value = "auto")]
+    pub color: ColorChoice,
+
+    #[clap(flatten)]
+    pub legacy: Legacy,
+}
+
+impl Args {
+    /// The subcommand to run, falling back to the one a legacy flag stands
+    /// for.
+    pub fn resolved_command(&self) -> Option<Command> {
+        self.command.clone().or_else(|| self.legacy.command())
+    }
+
+    /// Rejects the top level template next to a subcommand, which would
+    /// ignore it. Clap can't declare conflicts with subcommands.
+    pub fn check_conflicts(&self) -> Result<(), clap::Error> {
+        if self.command.is_some() && self.template.is_some() {
+            return Err(Args::command().error(
+                ErrorKind::ArgumentConflict,
+                "The argument '--template <TEMPLATE>' cannot be used with a subcommand, \
+                 use 'punch add --template <TEMPLATE>' instead",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// The flags from before the subcommands, hidden but still accepted. Flags
+/// changing the selected day, like `--comment` and `--remove`, apply to the
+/// bare blocks.
+#[derive(ClapArgs, Debug)]
+pub struct Legacy {
+    #[clap(long, hide = true)]
     pub raw: bool,
 
-    #[clap(long)]
+    #[clap(long, hide = true)]
     pub full: bool,
 
-    /// Edit BRF file with text editor
-    #[clap(short, long)]
+    #[clap(short, long, hide = true)]
     pub edit: bool,
 
-    /// Open BRF directory with default file browser
-    #[clap(long)]
+    #[clap(long, hide = true)]
     pub brf: bool,
 
-    /// Remove the provided blocks
-    #[clap(short, long)]
-    pub remove: bool,
+    #[clap(long, hide = true)]
+    pub print_path: bool,
 
-    /// Add blocks to a specific date
-    #[clap(short, long)]
-    pub day: Option<String>,
+    #[clap(short, long, hide = true, conflicts_with = "comment")]
+    pub remove: bool,
 
-    /// Add comment
-    #[clap(short, long)]
+    #[clap(short, long, hide = true)]
     pub comment: Option<String>,
 
-    /// Remove comment
-    #[clap(long)]
+    #[clap(long, hide = true)]
     pub clear_comment: bool,
 
-    /// Add blocks to yesterday
-    #[clap(short, long)]
+    #[clap(long, hide = true)]
+    pub timesheet: bool,
+
+    #[clap(long, hide = true, value_name = "DIR")]
+    pub import: Option<String>,
+}
+
+impl Legacy {
+    fn command(&self) -> Option<Command> {
+        if let Some(legacy_dir) = &self.import {
+            Some(Command::Import {
+                legacy_dir: legacy_dir.clone(),
+            })
+        } else if self.brf {
+            Some(Command::Open {
+                print_path: self.print_path,
+            })
+        } else if self.edit || self.print_path {
+            Some(Command::Edit {
+                print_path: self.print_path,
+            })
+        } else if self.timesheet {
+            Some(Command::Report)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the flags change the selected day.
+    pub fn changes_day(&self) -> bool {
+        self.comment.is_some() || self.clear_comment
+    }
+}
+
+/// Selects the days and month to work on. Selected days imply their month,
+/// so day and month options can't be combined.
+#[derive(ClapArgs, Debug)]
+pub struct Selection {
+    /// Select dates, e.g. 14, 14.2.2022, ranges like 14..18 or mon..wed and
+    /// comma separated lists of those
+    #[clap(short, long, alias = "days", global = true, conflicts_with_all = &["yesterday", "month", "previous", "next"])]
+    pub day: Option<String>,
+
+    /// Select yesterday
+    #[clap(short, long, global = true, conflicts_with_all = &["month", "previous", "next"])]
     pub yesterday: bool,
 
-    #[clap(short, long)]
+    /// Select a specific month, e.g. 2 or 2.2022
+    #[clap(short, long, global = true, conflicts_with_all = &["previous", "next"])]
     pub month: Option<String>,
 
-    /// Add blocks to previous month
-    #[clap(short, long)]
+    /// Select the previous month
+    #[clap(short, long, global = true, conflicts_with = "next")]
     pub previous: bool,
 
-    /// Add blocks to next month
-    #[clap(short, long)]
+    /// Select the next month
+    #[clap(short, long, global = true)]
     pub next: bool,
+}
 
-    /// Simulate the changes and don't write them to the BRF file
-    #[clap(long)]
-    pub dry_run: bool,
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Add blocks to the selected day
+    #[clap(allow_hyphen_values = true)]
+    Add {
+        #[clap(required_unless_present = "template")]
+        blocks: Vec<String>,
+
+        /// Also add the blocks of a schedule template from the config
+        #[clap(short, long)]
+        template: Option<String>,
+
+        /// Attach a note to the added blocks
+        #[clap(long)]
+        note: Option<String>,
+
+        /// Also set the comment of the day
+        #[clap(short, long)]
+        comment: Option<String>,
+
+        /// Also add a line to the comment of the day
+        #[clap(long, conflicts_with = "comment")]
+        append_comment: Option<String>,
+    },
+
+    /// Remove blocks from the selected day
+    #[clap(allow_hyphen_values = true)]
+    Rm {
+        #[clap(required = true)]
+        blocks: Vec<String>,
+    },
+
+    /// Show the selected month
+    Show {
+        /// Show the month as a chart instead
+        #[clap(long, arg_enum)]
+        chart: Option<ChartArg>,
+
+        /// Show the month as a calendar with the total of each day
+        #[clap(long, conflicts_with = "chart")]
+        calendar: bool,
+    },
+
+    /// Add the blocks of a schedule template to the weekdays of the month up
+    /// to today that don't have any entry yet
+    Fill { template: String },
+
+    /// Set, append to or clear the comment of the selected day
+    #[clap(group(ArgGroup::new("change").required(true).args(&["text", "clear"])))]
+    Comment {
+        text: Option<String>,
+
+        /// Add the text as a new line instead of replacing the comment
+        #[clap(long, conflicts_with = "clear")]
+        append: bool,
+
+        /// Remove the comment
+        #[clap(long)]
+        clear: bool,
+    },
+
+    /// List the comments and block notes of the month
+    Notes,
+
+    /// Search comments, block notes and projects in all BRF files of the
+    /// card, restricted to the selected days or month if given
+    Search {
+        query: String,
+
+        /// Match the query as a regular expression
+        #[clap(long)]
+        regex: bool,
+    },
+
+    /// Print averages, block length distribution and streaks of all BRF
+    /// files of the card, restricted to the selected days or month if given
+    Stats,
+
+    /// Edit the BRF file of the selected month with a text editor
+    Edit {
+        /// Print the path of the BRF file instead
+        #[clap(long)]
+        print_path: bool,
+    },
+
+    /// Open the BRF directory with the default file browser
+    Open {
+        /// Print the path of the BRF directory instead
+        #[clap(long)]
+        print_path: bool,
+    },
+
+    /// Print a printable HTML timesheet with signature lines for the month
+    Report,
+
+    /// Write the month in BRF format to a file or stdout
+    Export {
+        #[clap(value_name = "FILE")]
+        path: Option<String>,
+    },
+
+    /// Import a hours directory of the original Ruby punch, including cards
+    Import {
+        #[clap(value_name = "DIR")]
+        legacy_dir: String,
+    },
+
+    /// Check the month for missing breaks, overly long days and short rest
+    /// periods
+    Check,
+
+    /// Punch many corrections at once in an interactive shell
+    Shell,
+
+    /// Browse and edit months in an interactive terminal UI
+    Tui,
+
+    /// Print the running block and today's total
+    Status,
+
+    /// Close the running block now and start a new one
+    Switch {
+        /// Project of the new block
+        project: Option<String>,
+    },
+
+    /// Print a shell completion script, e.g. `source <(punch completions bash)`
+    Completions {
+        #[clap(arg_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Print dates, projects or cards from the hours directory for completion
+    #[clap(hide = true)]
+    Candidates {
+        #[clap(arg_enum)]
+        kind: Candidates,
+    },
+}
+
+/// When to colour the terminal output.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colour if stdout is a terminal and `NO_COLOR` isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+/// The charts of `show --chart`.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartArg {
+    /// One line per day, blocks filled in on an hour axis
+    Timeline,
+    /// One bar per day, proportional to the total
+    Bars,
+}
+
+impl From<ChartArg> for Chart {
+    fn from(chart: ChartArg) -> Self {
+        match chart {
+            ChartArg::Timeline => Chart::Timeline,
+            ChartArg::Bars => Chart::Bars,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+        Args::try_parse_from([&["punch"], args].concat())
+    }
+
+    #[test]
+    fn test_definition() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn test_bare_blocks() {
+        let args = parse(&["8-12", "13-17"]).unwrap();
+        assert!(args.command.is_none());
+        assert_eq!(vec!["8-12", "13-17"], args.blocks);
+
+        let args = parse(&["--", "-15m"]).unwrap();
+        assert_eq!(vec!["-15m"], args.blocks);
+
+        let args = parse(&["-15m"]).unwrap();
+        assert_eq!(vec!["-15m"], args.blocks);
+
+        let args = parse(&["-y", "-15m@acme", "-c", "x"]).unwrap();
+        assert!(args.selection.yesterday);
+        assert_eq!(vec!["-15m@acme"], args.blocks);
+        assert_eq!(Some("x".to_string()), args.legacy.comment);
+    }
+
+    #[test]
+    fn test_legacy_flags() {
+        let args = parse(&["8-12", "-c", "x"]).unwrap();
+        assert!(args.resolved_command().is_none());
+        assert_eq!(vec!["8-12"], args.blocks);
+        assert_eq!(Some("x".to_string()), args.legacy.comment);
+
+        let args = parse(&["-r", "8-12", "-d", "14"]).unwrap();
+        assert!(args.legacy.remove);
+        assert_eq!(vec!["8-12"], args.blocks);
+
+        assert!(matches!(
+            parse(&["--edit"]).unwrap().resolved_command(),
+            Some(Command::Edit { print_path: false })
+        ));
+        assert!(matches!(
+            parse(&["--print-path"]).unwrap().resolved_command(),
+            Some(Command::Edit { print_path: true })
+        ));
+        assert!(matches!(
+            parse(&["--brf", "--print-path"])
+                .unwrap()
+                .resolved_command(),
+            Some(Command::Open { print_path: true })
+        ));
+        assert!(matches!(
+            parse(&["--timesheet", "-p"]).unwrap().resolved_command(),
+            Some(Command::Report)
+        ));
+        assert!(matches!(
+            parse(&["--import", "old"]).unwrap().resolved_command(),
+            Some(Command::Import { .. })
+        ));
+        assert!(parse(&["--raw", "--full"])
+            .unwrap()
+            .resolved_command()
+            .is_none());
+    }
+
+    #[test]
+    fn test_global_selection() {
+        let args = parse(&["rm", "8-12", "--day", "14", "--card", "acme"]).unwrap();
+        assert_eq!(Some("14".to_string()), args.selection.day);
+        assert_eq!(Some("acme".to_string()), args.card);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        assert!(parse(&["-y", "-p"]).is_err());
+        assert!(parse(&["-d", "14", "-m", "2"]).is_err());
+        assert!(parse(&["-p", "-n"]).is_err());
+        assert!(parse(&["comment", "sick", "--clear"]).is_err());
+        assert!(parse(&["comment"]).is_err());
+        assert!(parse(&["comment", "--append", "--clear"]).is_err());
+        assert!(parse(&["add", "8", "-c", "a", "--append-comment", "b"]).is_err());
+        assert!(parse(&["add"]).is_err());
+        assert!(parse(&["add", "-t", "standard"]).is_ok());
+        assert!(parse(&["show", "--chart", "bars", "--calendar"]).is_err());
+        assert!(parse(&["8-12", "-r", "-c", "x"]).is_err());
+        assert!(parse(&["-t", "standard", "show"])
+            .unwrap()
+            .check_conflicts()
+            .is_err());
+        assert!(parse(&["-t", "standard"])
+            .unwrap()
+            .check_conflicts()
+            .is_ok());
+    }
 }
@@ -1,180 +1,625 @@
 use chrono::prelude::*;
+use colored::control;
+use crossterm::terminal;
+use crossterm::tty::IsTty;
+use punch::block::Block;
+use punch::card::PunchCard;
+use punch::chart;
+use punch::chart::Chart;
+use punch::compliance;
+use punch::compliance::Violation;
+use punch::day::Day;
+use punch::fmt;
+use punch::fmt::OutputMode;
+use punch::infer;
+use punch::month::Month;
+use punch::parse;
+use punch::parse::ParseError;
+use punch::parse::PROJECT_SEP;
+use punch::rounding::RoundingApply;
+use punch::search::Search;
+use punch::stats;
+use punch::stats::Stats;
+use punch::store;
+use punch::theme::Palette;
+use punch::timesheet;
 use std::collections::HashSet;
 use std::env::var;
 use std::fs;
-use std::fs::OpenOptions;
-use std::io::prelude::*;
+use std::io;
 use std::path::Path;
-use std::process::Command;
+use std::process;
 use std::process::ExitStatus;
 
-use crate::block::Block;
 use crate::cli::Args;
-use crate::fmt;
-use crate::fmt::OutputMode;
-use crate::infer;
-use crate::month::Month;
+use crate::cli::ColorChoice;
+use crate::cli::Command;
+use crate::cli::Selection;
+use crate::completions;
+use crate::import;
+use crate::opener::Opener;
+use crate::shell::Shell;
+use crate::tui::Tui;
 
 const DEFAULT_EDITOR: &str = "vim";
-const DEFAULT_HOURS_DIR: &str = "./hours";
+const ANNOTATION_PAT: &str = "#";
+const ANNOTATION_HELP: &str = "# Fix the errors above and save, or quit without changes to abort.
+# Lines starting with '#' are ignored.
+";
 
-pub struct PunchCard {
-    hours_dir_path: String,
-    selected_dates: HashSet<NaiveDate>,
-    modified_dates: HashSet<NaiveDate>,
-}
+fn edit_brf(path: &Path) -> Result<ExitStatus, std::io::Error> {
+    let editor = match var("EDITOR") {
+        Ok(e) => e,
+        Err(_) => DEFAULT_EDITOR.to_string(),
+    };
 
-impl PunchCard {
-    pub fn new() -> PunchCard {
-        let hours_dir_path = match var("PUNCH_HOURS_DIR") {
-            Ok(dir) => dir,
-            Err(_) => DEFAULT_HOURS_DIR.to_string(),
-        };
+    process::Command::new(editor).arg(path).status()
+}
 
-        PunchCard {
-            hours_dir_path,
-            selected_dates: HashSet::new(),
-            modified_dates: HashSet::new(),
+/// Opens the BRF file in the editor until it's valid, annotating the errors
+/// at the top like `git commit` does. Valid files are normalized through the
+/// regular write path, so totals are recalculated.
+fn edit_month(card: &PunchCard, year: i32, month_number: u32, dry_run: bool) {
+    let path_str = card.brf_file_path(year, month_number);
+    let path = Path::new(&path_str);
+
+    loop {
+        let before = fs::read_to_string(path).unwrap_or_default();
+        let status = edit_brf(path).expect("Could not edit file");
+        let contents = strip_annotations(&fs::read_to_string(path).unwrap_or_default());
+
+        // Line numbers of annotated errors refer to the annotated file
+        let (errors, annotated_errors) =
+            match parse::try_parse_month(&contents, year as u16, month_number as u8) {
+                Ok(mut month) => {
+                    write_month(&mut month, card, dry_run);
+                    return;
+                }
+                Err(e) => {
+                    let annotated = ParseError {
+                        line: e.line + ANNOTATION_HELP.lines().count() + 1,
+                        ..e.clone()
+                    };
+                    (vec![e.to_string()], vec![annotated.to_string()])
+                }
+            };
+        let annotated = annotate(&contents, &annotated_errors);
+
+        // Nothing changed, so the user gave up
+        if !status.success() || annotated == before {
+            fs::write(path, &contents).expect("Could not write file");
+            eprintln!("Invalid BRF file {}:", path.display());
+            errors.iter().for_each(|e| eprintln!("  {}", e));
+            process::exit(1);
         }
-    }
 
-    pub fn brf_file_path(&self, year: i32, month: u32) -> String {
-        format!("{}/{}-{}.txt", self.hours_dir_path, year, month)
+        fs::write(path, annotated).expect("Could not write file");
     }
+}
+
+fn annotate(contents: &str, errors: &[String]) -> String {
+    let errors = errors
+        .iter()
+        .map(|e| format!("{} {}", ANNOTATION_PAT, e))
+        .collect::<Vec<_>>()
+        .join(fmt::NEWLINE);
+
+    format!("{}{}{}{}", errors, fmt::NEWLINE, ANNOTATION_HELP, contents)
+}
 
-    pub fn select_date(&mut self, date: &NaiveDate) {
-        self.selected_dates.insert(*date);
+fn strip_annotations(contents: &str) -> String {
+    contents
+        .lines()
+        .filter(|l| !l.trim_start().starts_with(ANNOTATION_PAT))
+        .map(|l| format!("{}{}", l, fmt::NEWLINE))
+        .collect()
+}
+
+fn write_brf(month: &Month, card: &PunchCard, path: &Path, dry_run: bool) {
+    if dry_run {
+        return;
     }
 
-    pub fn modify_date(&mut self, date: &NaiveDate) {
-        self.modified_dates.insert(*date);
+    fs::write(path, fmt::format_month(month, card, OutputMode::File))
+        .expect("Could not write file");
+}
+
+/// Reads the BRF file of the given month, creating it if it doesn't exist
+/// yet.
+pub fn read_month(card: &PunchCard, year: i32, month_number: u32) -> Month {
+    store::read_month(card, year, month_number).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Dates given explicitly by the selection options, `None` if the selection
+/// was left to the defaults.
+fn selected_dates(
+    selection: &Selection,
+    dates: &[NaiveDate],
+    year: i32,
+    month: u32,
+) -> Option<HashSet<NaiveDate>> {
+    if selection.day.is_some() || selection.yesterday {
+        Some(dates.iter().copied().collect())
+    } else if selection.month.is_some() || selection.previous || selection.next {
+        let first = NaiveDate::from_ymd(year, month, 1);
+        Some(
+            first
+                .iter_days()
+                .take_while(|d| d.month() == month)
+                .collect(),
+        )
+    } else {
+        None
     }
+}
+
+/// Reads all valid BRF files of the selected card, sorted by month.
+pub fn read_all_months(card: &PunchCard) -> Vec<Month> {
+    store::read_all_months(card).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    pub fn was_selected(&self, date: &NaiveDate) -> bool {
-        self.selected_dates.contains(date)
+/// Cleans up the month and writes it to its BRF file, which recalculates all
+/// totals.
+pub fn write_month(month: &mut Month, card: &PunchCard, dry_run: bool) {
+    if dry_run {
+        month.cleanup();
+        return;
     }
 
-    pub fn was_modified(&self, date: &NaiveDate) -> bool {
-        self.modified_dates.contains(date)
+    store::write_month(month, card).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Infers a block from a CLI shorthand, rounding it if configured to round
+/// when punching.
+pub fn infer_block(card: &PunchCard, block_str: &str, day: &Day) -> Block {
+    try_infer_block(card, block_str, day).unwrap_or_else(|e| fail(e))
+}
+
+pub fn try_infer_block(card: &PunchCard, block_str: &str, day: &Day) -> Result<Block, String> {
+    let block = infer::try_infer_block(block_str, day, &card.now())?;
+    match card.config.rounding() {
+        Some(r) if r.apply == RoundingApply::Punch => Ok(r.round_new_block(&block, day)),
+        _ => Ok(block),
     }
+}
 
-    pub fn has_modifications(&self) -> bool {
-        !self.modified_dates.is_empty()
+fn print_status(card: &PunchCard, month: &mut Month, date: &NaiveDate) {
+    let now = card.now();
+    month.add_day(date);
+    let day = month.find_day_by_date(date).unwrap();
+
+    match day.find_running_block(&now) {
+        Some(b) => println!(
+            "Running since {}{} ({})",
+            fmt::format_block_date_as(&b.from, card.config.precision()),
+            b.project
+                .as_ref()
+                .map(|p| format!("{}{}", PROJECT_SEP, p))
+                .unwrap_or_default(),
+            fmt::format_term_duration(&b.live_duration(&now), card)
+        ),
+        None => println!("Not running"),
     }
+
+    let mark = if day.find_running_block(&now).is_some() {
+        fmt::PROVISIONAL_MARK
+    } else {
+        ""
+    };
+    println!(
+        "Today: {}{}",
+        fmt::format_term_duration(&day.live_duration(&now), card),
+        mark
+    );
 }
 
-fn open_dir(path: &str) -> Result<ExitStatus, std::io::Error> {
-    Command::new("open").arg(path).status()
+/// Checks the month, taking the last day of the previous month into account
+/// for rest periods.
+fn check_compliance(month: &Month, card: &PunchCard) -> Vec<Violation> {
+    // An unreadable or invalid previous month only skips the cross-month
+    // check, it's reported when that month is used
+    let (prev_month, prev_year) = infer::prev_month((month.month as u32, month.year as i32));
+    let mut days = fs::read_to_string(card.brf_file_path(prev_year, prev_month))
+        .ok()
+        .and_then(|c| parse::try_parse_month(&c, prev_year as u16, prev_month as u8).ok())
+        .and_then(|m| m.sorted_days().pop())
+        .into_iter()
+        .collect::<Vec<_>>();
+    days.extend(month.sorted_days());
+
+    compliance::check_days(&days)
+        .into_iter()
+        .filter(|v| v.date.month() == month.month as u32)
+        .collect()
 }
 
-fn edit_brf(path: &Path) -> Result<ExitStatus, std::io::Error> {
-    let editor = match var("EDITOR") {
-        Ok(e) => e,
-        Err(_) => DEFAULT_EDITOR.to_string(),
-    };
+/// Applies the colour choice to all coloured output. `NO_COLOR` disables
+/// and `CLICOLOR_FORCE` enables colours in auto mode.
+fn apply_color_choice(choice: ColorChoice, card: &mut PunchCard) {
+    let enabled = color_enabled(
+        choice,
+        var("NO_COLOR").ok().as_deref(),
+        var("CLICOLOR_FORCE").ok().as_deref(),
+        io::stdout().is_tty(),
+    );
+    control::set_override(enabled);
+    if !enabled {
+        card.theme.palette = Palette::Plain;
+    }
+}
 
-    Command::new(editor).arg(path).status()
+/// Width of the terminal, or the default width if stdout isn't one.
+fn terminal_width() -> usize {
+    terminal::size()
+        .map(|(width, _)| width as usize)
+        .unwrap_or(chart::DEFAULT_WIDTH)
 }
 
-fn write_brf(month: &Month, card: &PunchCard, path: &Path, dry_run: bool) {
-    if dry_run {
-        return;
+fn color_enabled(
+    choice: ColorChoice,
+    no_color: Option<&str>,
+    clicolor_force: Option<&str>,
+    is_terminal: bool,
+) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto if no_color.map_or(false, |v| !v.is_empty()) => false,
+        ColorChoice::Auto if clicolor_force.map_or(false, |v| v != "0") => true,
+        ColorChoice::Auto => is_terminal,
     }
+}
 
-    fs::write(path, fmt::format_month(month, card, OutputMode::File))
-        .expect("Could not write file");
+/// Reports invalid input and exits, without the backtrace hint of a panic.
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
 }
 
-pub fn punch(args: &Args) {
-    let mut date = Local::now().naive_local().date();
-    let mut year = date.year();
-    let mut month_number = date.month();
+/// Resolves the selected days and month. Selected days imply the month of
+/// the first one.
+fn select(selection: &Selection) -> (Vec<NaiveDate>, i32, u32) {
+    let today = Local::now().naive_local().date();
+    let mut dates = vec![today];
 
-    let mut card = PunchCard::new();
+    if selection.yesterday {
+        dates = vec![today.pred()];
+    }
 
-    if args.brf {
-        open_dir(&card.hours_dir_path).expect("Could not open hours directory");
-        return;
+    if let Some(d) = &selection.day {
+        dates = infer::try_infer_dates(d, &today).unwrap_or_else(|e| fail(e));
+        if dates.is_empty() {
+            fail(format!("No days selected by {}", d));
+        }
     }
 
-    if args.yesterday {
-        date = date.pred();
+    let date = dates[0];
+    let mut month_number = date.month();
+    let mut year = date.year();
+
+    if selection.previous {
+        (month_number, year) = infer::prev_month((month_number, year));
+    } else if selection.next {
+        (month_number, year) = infer::next_month((month_number, year));
     }
 
-    if let Some(d) = &args.day {
-        date = infer::infer_date(d, &date);
+    if let Some(m) = &selection.month {
+        (month_number, year) =
+            infer::try_infer_month(m, (month_number, year)).unwrap_or_else(|e| fail(e));
     }
 
-    if args.previous {
-        let my = infer::prev_month((month_number, year));
-        month_number = my.0;
-        year = my.1;
-    } else if args.next {
-        let my = infer::next_month((month_number, year));
-        month_number = my.0;
-        year = my.1;
+    (dates, year, month_number)
+}
+
+/// Writes the month in BRF format to `path`, or to stdout without a path.
+fn export_month(month: &mut Month, card: &PunchCard, path: Option<&str>, dry_run: bool) {
+    month.cleanup();
+    match path {
+        Some(p) => write_brf(month, card, Path::new(p), dry_run),
+        None => print!("{}", fmt::format_month(month, card, OutputMode::File)),
     }
+}
 
-    if let Some(m) = &args.month {
-        let my = infer::infer_month(m, (month_number, year));
-        month_number = my.0;
-        year = my.1;
+fn show_month(
+    card: &mut PunchCard,
+    year: i32,
+    month_number: u32,
+    chart: Option<Chart>,
+    calendar: bool,
+) {
+    let month = read_month(card, year, month_number);
+    card.flag_violations(check_compliance(&month, card));
+
+    match chart {
+        Some(c) => println!("{}", chart::format_chart(&month, card, c, terminal_width())),
+        None if calendar => println!("{}", chart::format_calendar(&month, card, &Local::now())),
+        None => println!("{}", fmt::format_month(&month, card, OutputMode::Term)),
     }
+}
 
-    card.select_date(&date);
+/// Applies a change to each selected day, then prints and writes the
+/// affected months.
+fn change_days<F: FnMut(&mut Day, &PunchCard)>(
+    card: &mut PunchCard,
+    dates: &[NaiveDate],
+    dry_run: bool,
+    mut change: F,
+) {
+    let mut months: Vec<Month> = vec![];
+
+    for date in dates {
+        let (year, month_number) = (date.year(), date.month());
+        let index = match months
+            .iter()
+            .position(|m| m.year as i32 == year && m.month as u32 == month_number)
+        {
+            Some(i) => i,
+            None => {
+                months.push(read_month(card, year, month_number));
+                months.len() - 1
+            }
+        };
 
-    let path_str = card.brf_file_path(year, month_number);
-    let file_path = Path::new(&path_str);
+        let month = &mut months[index];
+        month.add_day(date);
+        change(month.find_day_by_date(date).unwrap(), card);
+        card.modify_date(date);
+    }
 
-    if args.edit {
-        edit_brf(file_path).expect("Could not edit file");
-        return;
+    for month in months.iter_mut() {
+        card.flag_violations(check_compliance(month, card));
+        println!("{}", fmt::format_month(month, card, OutputMode::Term));
+        write_month(month, card, dry_run);
     }
+}
 
-    let mut file = OpenOptions::new()
-        .read(true)
-        .create(true)
-        .write(true)
-        .open(file_path)
-        .unwrap();
+/// Block shorthands of a template in the config.
+fn template_blocks(card: &PunchCard, name: &str) -> Vec<String> {
+    match card.config.template(name) {
+        Some(blocks) => blocks,
+        None => fail(format!("Unknown template {}", name)),
+    }
+}
 
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .expect("Could not read current BRF file");
+/// Adds or removes the blocks given as CLI shorthands, attaching the note to
+/// added blocks.
+fn punch_blocks(
+    day: &mut Day,
+    card: &PunchCard,
+    blocks: &[String],
+    note: Option<&str>,
+    remove: bool,
+) {
+    blocks.iter().for_each(|block_str| {
+        let mut block = infer_block(card, block_str, day);
+        block.note = note.map(|n| n.to_string());
+        if remove {
+            day.remove_block(&block);
+        } else {
+            day.add_block(&block);
+        }
+    });
+}
 
-    let mut month = Month::from_brf(&contents, year as u16, month_number as u8);
-    month.add_day(&date);
-    let day = month.find_day_by_date(&date).unwrap();
+pub fn punch(args: &Args) {
+    let mut card = PunchCard::new();
+    apply_color_choice(args.color, &mut card);
 
-    if args.clear_comment {
-        day.clear_comment();
-        card.modify_date(&date);
+    if let Some(c) = &args.card {
+        card.select_card(c);
     }
 
-    match &args.comment {
-        Some(comment) => {
-            day.add_comment(comment);
-            card.modify_date(&date);
+    let (dates, year, month_number) = select(&args.selection);
+    dates.iter().for_each(|d| card.select_date(d));
+    let date = dates[0];
+
+    match &args.resolved_command() {
+        Some(Command::Completions { shell }) => completions::print_completions(*shell),
+        Some(Command::Candidates { kind }) => completions::print_candidates(&card, *kind),
+        Some(Command::Import { legacy_dir }) => {
+            import::import_hours(Path::new(legacy_dir), &card, args.dry_run)
         }
-        None => (),
-    };
+        Some(Command::Open { print_path: true }) => println!("{}", card.card_dir_path()),
+        Some(Command::Open { print_path: false }) => {
+            Opener::new(&card.config).open(&card.card_dir_path())
+        }
+        Some(Command::Edit { print_path: true }) => {
+            println!("{}", card.brf_file_path(year, month_number))
+        }
+        Some(Command::Edit { print_path: false }) => {
+            edit_month(&card, year, month_number, args.dry_run)
+        }
+        Some(Command::Report) => {
+            let month = read_month(&card, year, month_number);
+            println!("{}", timesheet::format_timesheet(&month, &card.config));
+        }
+        Some(Command::Export { path }) => {
+            let mut month = read_month(&card, year, month_number);
+            export_month(&mut month, &card, path.as_deref(), args.dry_run);
+        }
+        Some(Command::Shell) => {
+            let month = read_month(&card, year, month_number);
+            Shell::new(card, month, &date, args.dry_run).run();
+        }
+        Some(Command::Tui) => {
+            let month = read_month(&card, year, month_number);
+            let mut tui = Tui::new(card, month, &date, args.dry_run);
+            tui.run().expect("Could not run terminal UI");
+        }
+        Some(Command::Status) => {
+            let mut month = read_month(&card, year, month_number);
+            print_status(&card, &mut month, &date);
+        }
+        Some(Command::Check) => {
+            let month = read_month(&card, year, month_number);
+            let violations = check_compliance(&month, &card);
+            violations.iter().for_each(|v| {
+                println!(
+                    "{}",
+                    v.format(
+                        &card.locale,
+                        card.config.duration_format(OutputMode::Term.name())
+                    )
+                )
+            });
+            if !violations.is_empty() {
+                process::exit(1);
+            }
+        }
+        Some(Command::Show { chart, calendar }) => show_month(
+            &mut card,
+            year,
+            month_number,
+            chart.map(Chart::from),
+            *calendar,
+        ),
+        Some(Command::Add {
+            blocks,
+            template,
+            note,
+            comment,
+            append_comment,
+        }) => {
+            let mut blocks = blocks.clone();
+            if let Some(t) = template {
+                blocks.extend(template_blocks(&card, t));
+            }
 
-    if !args.blocks.is_empty() {
-        args.blocks.iter().for_each(|block_str| {
-            let block = Block::parse(block_str, day);
-            if args.remove {
-                day.remove_block(&block);
-            } else {
-                day.add_block(&block);
+            change_days(&mut card, &dates, args.dry_run, |day, card| {
+                if let Some(c) = comment {
+                    day.add_comment(c);
+                }
+                if let Some(c) = append_comment {
+                    day.append_comment(c);
+                }
+                punch_blocks(day, card, &blocks, note.as_deref(), false);
+            });
+        }
+        Some(Command::Fill { template }) => {
+            let blocks = template_blocks(&card, template);
+            let today = Local::now().naive_local().date();
+            let missing =
+                read_month(&card, year, month_number).missing_workdays(&today, card.holidays());
+            if missing.is_empty() {
+                println!("No weekdays to fill");
+                return;
             }
-        });
 
-        card.modify_date(&date);
+            missing.iter().for_each(|d| card.select_date(d));
+            change_days(&mut card, &missing, args.dry_run, |day, card| {
+                punch_blocks(day, card, &blocks, None, false);
+            });
+        }
+        Some(Command::Rm { blocks }) => {
+            change_days(&mut card, &dates, args.dry_run, |day, card| {
+                punch_blocks(day, card, blocks, None, true);
+            });
+        }
+        Some(Command::Comment {
+            text,
+            append,
+            clear,
+        }) => {
+            change_days(&mut card, &dates, args.dry_run, |day, _| match text {
+                Some(t) if *append => day.append_comment(t),
+                Some(t) if !clear => day.add_comment(t),
+                _ => day.clear_comment(),
+            });
+        }
+        Some(Command::Search { query, regex }) => {
+            let mut search = Search::new(query, *regex)
+                .unwrap_or_else(|e| fail(format!("Invalid query: {}", e)));
+
+            if let Some(selected) = selected_dates(&args.selection, &dates, year, month_number) {
+                search.restrict(selected);
+            }
+
+            let days = search.find(&read_all_months(&card));
+            let now = Local::now();
+            days.iter().for_each(|d| {
+                println!(
+                    "{}",
+                    fmt::format_day(d, 0, 0, &card, OutputMode::Term, &now)
+                )
+            });
+            println!(
+                "{}Matches: {}{}Total: {}",
+                fmt::NEWLINE,
+                days.len(),
+                fmt::SPACER,
+                fmt::format_term_duration(&days.iter().map(|d| d.duration()).sum(), &card)
+            );
+        }
+        Some(Command::Stats) => {
+            let selected = selected_dates(&args.selection, &dates, year, month_number);
+            let days = read_all_months(&card)
+                .iter()
+                .flat_map(|m| m.sorted_days())
+                .filter(|d| match &selected {
+                    Some(s) => s.contains(&d.date),
+                    None => true,
+                })
+                .collect::<Vec<_>>();
+
+            println!(
+                "{}",
+                stats::format_stats(
+                    &Stats::from_days(&days),
+                    &card.locale,
+                    card.config.duration_format(OutputMode::Term.name()),
+                )
+            );
+        }
+        Some(Command::Notes) => {
+            let month = read_month(&card, year, month_number);
+            println!("{}", fmt::format_notes(&month, &card.locale));
+        }
+        Some(Command::Switch { project }) => {
+            let now = card.now();
+            if dates != [now.date().naive_local()] {
+                fail("Blocks can only be switched today");
+            }
+
+            change_days(&mut card, &dates, args.dry_run, |day, _| {
+                day.switch_block(&now, project.clone())
+                    .unwrap_or_else(|e| fail(e));
+            });
+        }
+        None if args.blocks.is_empty() && args.template.is_none() && !args.legacy.changes_day() => {
+            show_month(&mut card, year, month_number, None, false)
+        }
+        None => {
+            let mut blocks = args.blocks.clone();
+            if let Some(t) = &args.template {
+                blocks.extend(template_blocks(&card, t));
+            }
+
+            let legacy = &args.legacy;
+            change_days(&mut card, &dates, args.dry_run, |day, card| {
+                if legacy.clear_comment {
+                    day.clear_comment();
+                }
+                if let Some(c) = &legacy.comment {
+                    day.add_comment(c);
+                }
+                punch_blocks(day, card, &blocks, None, legacy.remove);
+            });
+        }
     }
+}
 
-    println!("{}", fmt::format_month(&month, &card, OutputMode::Term));
-    if card.has_modifications() && !args.dry_run {
-        month.cleanup();
-        write_brf(&month, &card, file_path, args.dry_run);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_enabled() {
+        assert!(color_enabled(ColorChoice::Auto, None, None, true));
+        assert!(!color_enabled(ColorChoice::Auto, None, None, false));
+        assert!(!color_enabled(ColorChoice::Auto, Some("1"), None, true));
+        assert!(color_enabled(ColorChoice::Auto, Some(""), None, true));
+        assert!(color_enabled(ColorChoice::Auto, None, Some("1"), false));
+        assert!(color_enabled(ColorChoice::Always, Some("1"), None, false));
+        assert!(!color_enabled(ColorChoice::Never, None, None, true));
     }
 }
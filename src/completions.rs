@@ -0,0 +1,210 @@
+use clap::ArgEnum;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use punch::card::PunchCard;
+use punch::month::Month;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+
+use crate::cli::Args;
+use crate::clock;
+use crate::shell::COMPLETION_DATE_FORMAT;
+
+/// Values completed dynamically by the shell scripts through the hidden
+/// `punch candidates` command.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Candidates {
+    Dates,
+    Projects,
+    Cards,
+}
+
+/// Completes `--day` and `--card` values and `@project` block suffixes.
+/// Bash splits words at `@` by default, in that case only the project is
+/// completed.
+const BASH_DYNAMIC: &str = r#"
+_punch_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local word="${COMP_LINE:0:COMP_POINT}"
+    word="${word##* }"
+
+    case "$prev" in
+        -d|--day)
+            COMPREPLY=($(compgen -W "$(punch candidates dates 2>/dev/null)" -- "$cur"))
+            return 0
+            ;;
+        --card)
+            COMPREPLY=($(compgen -W "$(punch candidates cards 2>/dev/null)" -- "$cur"))
+            return 0
+            ;;
+        switch)
+            COMPREPLY=($(compgen -W "$(punch candidates projects 2>/dev/null)" -- "$cur"))
+            return 0
+            ;;
+    esac
+
+    if [[ "$word" == *@* ]]; then
+        local prefix=""
+        [[ "$COMP_WORDBREAKS" == *@* ]] || prefix="${word%@*}@"
+        COMPREPLY=($(compgen -P "$prefix" -W "$(punch candidates projects 2>/dev/null)" -- "${word##*@}"))
+        return 0
+    fi
+
+    _punch "$@"
+}
+
+complete -F _punch_dynamic -o bashdefault -o default punch"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_punch_dynamic() {
+    case "${words[CURRENT-1]}" in
+        -d|--day)
+            compadd -- ${(f)"$(punch candidates dates 2>/dev/null)"}
+            return
+            ;;
+        --card)
+            compadd -- ${(f)"$(punch candidates cards 2>/dev/null)"}
+            return
+            ;;
+        switch)
+            compadd -- ${(f)"$(punch candidates projects 2>/dev/null)"}
+            return
+            ;;
+    esac
+
+    if [[ "$PREFIX" == *@* ]]; then
+        compset -P '*@'
+        compadd -- ${(f)"$(punch candidates projects 2>/dev/null)"}
+        return
+    fi
+
+    _punch "$@"
+}
+
+compdef _punch_dynamic punch"#;
+
+const FISH_DYNAMIC: &str = r#"
+complete -c punch -s d -l day -x -a '(punch candidates dates 2>/dev/null)'
+complete -c punch -l card -x -a '(punch candidates cards 2>/dev/null)'
+complete -c punch -n '__fish_seen_subcommand_from switch' -x -a '(punch candidates projects 2>/dev/null)'
+complete -c punch -n 'string match -q "*@*" -- (commandline -ct)' -x -a '(string replace -r "@[^@]*\$" "" -- (commandline -ct))@(punch candidates projects 2>/dev/null)'"#;
+
+/// Prints the completion script generated from `cli::Args`, extended with
+/// the dynamic completions for the supported shells.
+pub fn print_completions(shell: Shell) {
+    let mut command = Args::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+
+    if let Some(dynamic) = dynamic_completions(shell) {
+        println!("{}", dynamic);
+    }
+}
+
+fn dynamic_completions(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(BASH_DYNAMIC),
+        Shell::Zsh => Some(ZSH_DYNAMIC),
+        Shell::Fish => Some(FISH_DYNAMIC),
+        _ => None,
+    }
+}
+
+/// Prints the candidates read from the hours directory, one per line.
+pub fn print_candidates(card: &PunchCard, kind: Candidates) {
+    let candidates = match kind {
+        Candidates::Dates => collect_dates(&clock::read_all_months(card)),
+        Candidates::Projects => collect_projects(&clock::read_all_months(card)),
+        Candidates::Cards => collect_cards(card),
+    };
+
+    candidates.iter().for_each(|c| println!("{}", c));
+}
+
+/// Dates with entries, most recent first.
+fn collect_dates(months: &[Month]) -> Vec<String> {
+    let dates = months
+        .iter()
+        .flat_map(|m| m.days.keys())
+        .collect::<BTreeSet<_>>();
+
+    dates
+        .into_iter()
+        .rev()
+        .map(|d| d.format(COMPLETION_DATE_FORMAT).to_string())
+        .collect()
+}
+
+fn collect_projects(months: &[Month]) -> Vec<String> {
+    months
+        .iter()
+        .flat_map(|m| m.days.values())
+        .flat_map(|d| d.blocks.iter())
+        .filter_map(|b| b.project.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn collect_cards(card: &PunchCard) -> Vec<String> {
+    let mut cards = card.config.cards().into_iter().collect::<BTreeSet<_>>();
+
+    if let Ok(entries) = fs::read_dir(card.hours_dir_path()) {
+        cards.extend(
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| !name.starts_with('.')),
+        );
+    }
+
+    cards.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn months() -> Vec<Month> {
+        let january = r#"
+            January 2022
+
+            31.01.22   08:00-12:00@acme   Total: 04:00
+
+            Total: 04:00
+        "#;
+        let february = r#"
+            February 2022
+
+            01.02.22   08:00-12:00@initech   13:00-14:00@acme   14:00-15:00   Total: 06:00
+
+            Total: 06:00
+        "#;
+
+        vec![
+            Month::from_brf(february, 2022, 2),
+            Month::from_brf(january, 2022, 1),
+        ]
+    }
+
+    #[test]
+    fn test_collect_dates() {
+        assert_eq!(vec!["01.02.2022", "31.01.2022"], collect_dates(&months()));
+    }
+
+    #[test]
+    fn test_collect_projects() {
+        assert_eq!(vec!["acme", "initech"], collect_projects(&months()));
+    }
+
+    #[test]
+    fn test_dynamic_completions() {
+        assert!(dynamic_completions(Shell::Bash)
+            .unwrap()
+            .contains("punch candidates projects"));
+        assert_eq!(None, dynamic_completions(Shell::PowerShell));
+    }
+}
@@ -0,0 +1,276 @@
+use chrono::prelude::*;
+use std::fmt;
+use std::time::Duration;
+
+use crate::day::Day;
+use crate::fmt::format_duration_as;
+use crate::fmt::DurationFormat;
+use crate::fmt::Precision;
+use crate::locale::Locale;
+use crate::month::Month;
+
+const HOUR: u64 = 60 * 60;
+const MINUTE: u64 = 60;
+
+/// Work longer than this requires the break of the same index in
+/// `REQUIRED_BREAKS`.
+const BREAK_THRESHOLDS: [Duration; 2] =
+    [Duration::from_secs(6 * HOUR), Duration::from_secs(9 * HOUR)];
+const REQUIRED_BREAKS: [Duration; 2] = [
+    Duration::from_secs(30 * MINUTE),
+    Duration::from_secs(45 * MINUTE),
+];
+
+/// Shorter gaps between blocks don't count as a break.
+const MIN_BREAK: Duration = Duration::from_secs(15 * MINUTE);
+const MAX_DAY: Duration = Duration::from_secs(10 * HOUR);
+const MIN_REST: Duration = Duration::from_secs(11 * HOUR);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ViolationKind {
+    MissingBreak {
+        worked: Duration,
+        taken: Duration,
+        required: Duration,
+    },
+    TooLong {
+        worked: Duration,
+    },
+    ShortRest {
+        rest: Duration,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Violation {
+    pub date: NaiveDate,
+    pub kind: ViolationKind,
+}
+
+impl Violation {
+    /// Describes the violation with the date in the terminal date format.
+    pub fn format(&self, locale: &Locale, format: DurationFormat) -> String {
+        format!(
+            "{}: {}",
+            locale.format_date(&self.date),
+            self.kind.format(format)
+        )
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.format(&Locale::default(), DurationFormat::Clock)
+        )
+    }
+}
+
+impl ViolationKind {
+    /// Describes the violation with durations in the given format.
+    pub fn format(&self, format: DurationFormat) -> String {
+        let duration = |d: &Duration| format_duration_as(d, Precision::Minutes, format);
+        match self {
+            ViolationKind::MissingBreak {
+                worked,
+                taken,
+                required,
+            } => format!(
+                "{} worked with {} break, {} required",
+                duration(worked),
+                duration(taken),
+                duration(required)
+            ),
+            ViolationKind::TooLong { worked } => format!(
+                "{} worked, at most {} allowed",
+                duration(worked),
+                duration(&MAX_DAY)
+            ),
+            ViolationKind::ShortRest { rest } => format!(
+                "{} rest since the previous day, {} required",
+                duration(rest),
+                duration(&MIN_REST)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(DurationFormat::Clock))
+    }
+}
+
+/// Checks sorted days for missing breaks, overly long days and too little
+/// rest between consecutive days.
+pub fn check_days(days: &[Day]) -> Vec<Violation> {
+    let mut violations = days.iter().flat_map(check_day).collect::<Vec<_>>();
+
+    for pair in days.windows(2) {
+        if let Some(v) = check_rest(&pair[0], &pair[1]) {
+            violations.push(v);
+        }
+    }
+
+    violations.sort_by_key(|v| v.date);
+    violations
+}
+
+pub fn check_day(day: &Day) -> Vec<Violation> {
+    let mut violations = vec![];
+    let worked = day.duration();
+
+    if let Some(required) = required_break(&worked) {
+        let taken = break_duration(day);
+        if taken < required {
+            violations.push(Violation {
+                date: day.date,
+                kind: ViolationKind::MissingBreak {
+                    worked,
+                    taken,
+                    required,
+                },
+            });
+        }
+    }
+
+    if worked > MAX_DAY {
+        violations.push(Violation {
+            date: day.date,
+            kind: ViolationKind::TooLong { worked },
+        });
+    }
+
+    violations
+}
+
+fn required_break(worked: &Duration) -> Option<Duration> {
+    BREAK_THRESHOLDS
+        .iter()
+        .zip(REQUIRED_BREAKS.iter())
+        .rev()
+        .find(|(threshold, _)| *worked > **threshold)
+        .map(|(_, required)| *required)
+}
+
+/// Part of the required break that wasn't taken, deducted from the totals
+/// with `break_deduction = on`. The whole missing break is deducted, even if
+/// that brings the day below the threshold.
+pub fn missing_break(day: &Day) -> Duration {
+    required_break(&day.duration())
+        .map(|required| required.saturating_sub(break_duration(day)))
+        .unwrap_or_default()
+}
+
+pub fn month_missing_break(month: &Month) -> Duration {
+    month.days.values().map(missing_break).sum::<Duration>()
+}
+
+/// Sum of the gaps between blocks that are long enough to count as a break.
+pub fn break_duration(day: &Day) -> Duration {
+    day.blocks
+        .windows(2)
+        .filter_map(|pair| (pair[1].from - pair[0].to).to_std().ok())
+        .filter(|gap| *gap >= MIN_BREAK)
+        .sum::<Duration>()
+}
+
+fn check_rest(previous: &Day, day: &Day) -> Option<Violation> {
+    if previous.date.succ() != day.date {
+        return None;
+    }
+
+    let end = previous.blocks.iter().map(|b| b.to).max()?;
+    let start = day.blocks.iter().map(|b| b.from).min()?;
+    let rest = (start - end).to_std().unwrap_or_default();
+
+    if rest < MIN_REST {
+        Some(Violation {
+            date: day.date,
+            kind: ViolationKind::ShortRest { rest },
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_missing_break() {
+        let day = parse::parse_day("01.02.22  08:00-12:00  12:15-15:00  Total: 06:45");
+
+        assert_eq!(
+            vec![Violation {
+                date: day.date,
+                kind: ViolationKind::MissingBreak {
+                    worked: Duration::from_secs(6 * HOUR + 45 * MINUTE),
+                    taken: Duration::from_secs(15 * MINUTE),
+                    required: Duration::from_secs(30 * MINUTE),
+                }
+            }],
+            check_day(&day)
+        );
+    }
+
+    #[test]
+    fn test_missing_break_deduction() {
+        let day = parse::parse_day("01.02.22  08:00-12:00  12:15-15:00  Total: 06:45");
+        assert_eq!(Duration::from_secs(15 * MINUTE), missing_break(&day));
+
+        let day = parse::parse_day("01.02.22  08:00-18:00  Total: 10:00");
+        assert_eq!(Duration::from_secs(45 * MINUTE), missing_break(&day));
+
+        let day = parse::parse_day("01.02.22  08:00-14:00  Total: 06:00");
+        assert_eq!(Duration::from_secs(0), missing_break(&day));
+    }
+
+    #[test]
+    fn test_short_gaps_are_no_breaks() {
+        let day = parse::parse_day("01.02.22  08:00-12:00  12:10-15:00  Total: 06:50");
+        assert_eq!(Duration::from_secs(0), break_duration(&day));
+    }
+
+    #[test]
+    fn test_compliant_day() {
+        let day = parse::parse_day("01.02.22  08:00-12:00  12:45-18:00  Total: 09:15");
+        assert!(check_day(&day).is_empty());
+    }
+
+    #[test]
+    fn test_too_long_day() {
+        let day = parse::parse_day("01.02.22  07:00-12:00  13:00-19:00  Total: 11:00");
+        let violations = check_day(&day);
+
+        assert_eq!(1, violations.len());
+        assert_eq!(
+            "Tue   01.02.22: 11:00 worked, at most 10:00 allowed",
+            violations[0].to_string()
+        );
+        assert_eq!(
+            "Tue   01.02.22: 11.00 worked, at most 10.00 allowed",
+            violations[0].format(&Locale::default(), DurationFormat::Decimal(2))
+        );
+    }
+
+    #[test]
+    fn test_short_rest() {
+        let days = vec![
+            parse::parse_day("01.02.22  14:00-23:00  Total: 09:00"),
+            parse::parse_day("02.02.22  07:00-08:00  Total: 01:00"),
+            parse::parse_day("04.02.22  07:00-08:00  Total: 01:00"),
+        ];
+        let violations = check_days(&days);
+
+        assert_eq!(2, violations.len());
+        assert_eq!(
+            "Wed   02.02.22: 08:00 rest since the previous day, 11:00 required",
+            violations[1].to_string()
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::env::var;
+use std::fs;
+use std::time::Duration;
+
+use crate::fmt::DurationFormat;
+use crate::fmt::Precision;
+use crate::locale::Locale;
+use crate::parse;
+use crate::rounding::Rounding;
+use crate::rounding::RoundingApply;
+use crate::rounding::RoundingMode;
+use crate::rounding::RoundingScope;
+
+pub const CONFIG_FILE_NAME: &str = "punch.conf";
+pub const COMMENT_PAT: &str = "#";
+pub const DEFAULT_DAILY_TARGET: &str = "08:00";
+pub const TEMPLATE_PREFIX: &str = "template.";
+pub const HOLIDAY_DATE_FORMAT: &str = "%d.%m.%Y";
+pub const DURATION_FORMAT_PREFIX: &str = "duration_format.";
+pub const DEFAULT_DECIMAL_PLACES: usize = 2;
+pub const DURATION_FORMAT_OUTPUTS: [&str; 3] = ["term", "file", "report"];
+
+/// Simple `key = value` configuration, read from `$PUNCH_CONFIG` or from
+/// `punch.conf` in the hours directory.
+#[derive(Debug, Default)]
+pub struct Config {
+    entries: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(hours_dir_path: &str) -> Self {
+        let path = match var("PUNCH_CONFIG") {
+            Ok(path) => path,
+            Err(_) => format!("{}/{}", hours_dir_path, CONFIG_FILE_NAME),
+        };
+
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let entries = contents
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with(COMMENT_PAT))
+            .filter_map(|l| l.split_once('='))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(|v| v.as_str())
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.get("name")
+    }
+
+    /// Names of the configured cards, e.g. `cards = acme, initech`.
+    pub fn cards(&self) -> Vec<String> {
+        self.get("cards")
+            .map(|cards| {
+                cards
+                    .split(',')
+                    .map(|c| c.trim().to_string())
+                    .filter(|c| !c.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Holidays, e.g. `holidays = 24.12.2022, 25.12.2022`. They're
+    /// highlighted, not filled and don't count towards the target.
+    pub fn holidays(&self) -> Vec<NaiveDate> {
+        self.try_holidays().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_holidays(&self) -> Result<Vec<NaiveDate>, String> {
+        self.get("holidays")
+            .unwrap_or_default()
+            .split(',')
+            .map(|h| h.trim())
+            .filter(|h| !h.is_empty())
+            .map(|h| {
+                NaiveDate::parse_from_str(h, HOLIDAY_DATE_FORMAT)
+                    .map_err(|_| format!("Invalid holiday {}", h))
+            })
+            .collect()
+    }
+
+    /// Blocks of a named schedule template, e.g.
+    /// `template.standard = 08:00-12:00 13:00-17:00`.
+    pub fn template(&self, name: &str) -> Option<Vec<String>> {
+        self.get(&format!("{}{}", TEMPLATE_PREFIX, name))
+            .map(|blocks| blocks.split_whitespace().map(|b| b.to_string()).collect())
+    }
+
+    /// Hours expected per working day, e.g. `daily_target = 08:24`.
+    pub fn daily_target(&self) -> Duration {
+        self.try_daily_target().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_daily_target(&self) -> Result<Duration, String> {
+        let (hours, minutes) =
+            parse::try_parse_hm(self.get("daily_target").unwrap_or(DEFAULT_DAILY_TARGET))?;
+        Ok(Duration::from_secs((hours * 60 * 60 + minutes * 60) as u64))
+    }
+
+    /// Duration format of an output (`term`, `file` or `report`), e.g.
+    /// `duration_format = decimal` for all outputs or
+    /// `duration_format.report = decimal`, with `decimal_places = 2`.
+    pub fn duration_format(&self, output: &str) -> DurationFormat {
+        self.try_duration_format(output)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_duration_format(&self, output: &str) -> Result<DurationFormat, String> {
+        let format = self
+            .get(&format!("{}{}", DURATION_FORMAT_PREFIX, output))
+            .or_else(|| self.get("duration_format"));
+
+        match format {
+            None | Some("clock") => Ok(DurationFormat::Clock),
+            Some("decimal") => match self.get("decimal_places") {
+                None => Ok(DurationFormat::Decimal(DEFAULT_DECIMAL_PLACES)),
+                Some(p) => p
+                    .parse()
+                    .map(DurationFormat::Decimal)
+                    .map_err(|_| format!("Invalid decimal_places {}", p)),
+            },
+            Some(f) => Err(format!("Unknown duration format {}", f)),
+        }
+    }
+
+    /// Whether missing breaks are deducted from the totals,
+    /// `break_deduction = on | off`.
+    pub fn break_deduction(&self) -> bool {
+        self.try_break_deduction()
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_break_deduction(&self) -> Result<bool, String> {
+        match self.get("break_deduction") {
+            None | Some("off") => Ok(false),
+            Some("on") => Ok(true),
+            Some(b) => Err(format!("Invalid break_deduction {}", b)),
+        }
+    }
+
+    /// Precision of new blocks and totals, `precision = minutes | seconds`.
+    pub fn precision(&self) -> Precision {
+        self.try_precision().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_precision(&self) -> Result<Precision, String> {
+        match self.get("precision") {
+            None | Some("minutes") => Ok(Precision::Minutes),
+            Some("seconds") => Ok(Precision::Seconds),
+            Some(p) => Err(format!("Unknown precision {}", p)),
+        }
+    }
+
+    /// Rounding policy, e.g.
+    ///
+    /// ```text
+    /// rounding = 15
+    /// rounding_mode = nearest | up | down
+    /// rounding_scope = block | day
+    /// rounding_apply = report | punch
+    /// ```
+    pub fn rounding(&self) -> Option<Rounding> {
+        self.try_rounding().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_rounding(&self) -> Result<Option<Rounding>, String> {
+        let minutes = match self.get("rounding") {
+            Some(m) => m
+                .parse::<u64>()
+                .map_err(|_| format!("Could not parse rounding {}", m))?,
+            None => return Ok(None),
+        };
+        let mut rounding = Rounding::try_new(minutes)?;
+
+        rounding.mode = match self.get("rounding_mode") {
+            None | Some("nearest") => RoundingMode::Nearest,
+            Some("up") => RoundingMode::Up,
+            Some("down") => RoundingMode::Down,
+            Some(m) => return Err(format!("Unknown rounding mode {}", m)),
+        };
+
+        rounding.scope = match self.get("rounding_scope") {
+            None | Some("block") => RoundingScope::Block,
+            Some("day") => RoundingScope::Day,
+            Some(s) => return Err(format!("Unknown rounding scope {}", s)),
+        };
+
+        rounding.apply = match self.get("rounding_apply") {
+            None | Some("report") => RoundingApply::Report,
+            Some("punch") => RoundingApply::Punch,
+            Some(a) => return Err(format!("Unknown rounding apply {}", a)),
+        };
+
+        Ok(Some(rounding))
+    }
+
+    /// Checks all values with a fixed format, so the getters above don't
+    /// panic afterwards.
+    pub fn validate(&self) -> Result<(), String> {
+        self.try_holidays()?;
+        self.try_daily_target()?;
+        self.try_break_deduction()?;
+        self.try_precision()?;
+        self.try_rounding()?;
+        for output in DURATION_FORMAT_OUTPUTS {
+            self.try_duration_format(output)?;
+        }
+        Locale::try_from_config(self)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            r#"
+            # Who signs the timesheet
+            name = Jane Doe
+            daily_target = 08:24
+            cards = acme, initech
+            template.standard = 08:00-12:00  13:00-17:00@acme
+            "#,
+        );
+
+        assert_eq!(Some("Jane Doe"), config.name());
+        assert_eq!(
+            Duration::from_secs(8 * 60 * 60 + 24 * 60),
+            config.daily_target()
+        );
+        assert_eq!(vec!["acme", "initech"], config.cards());
+        assert_eq!(
+            Some(vec![
+                "08:00-12:00".to_string(),
+                "13:00-17:00@acme".to_string()
+            ]),
+            config.template("standard")
+        );
+        assert_eq!(None, config.template("short"));
+        assert_eq!(None, config.rounding());
+    }
+
+    #[test]
+    fn test_parse_rounding() {
+        let config = Config::parse("rounding = 15\nrounding_mode = up\nrounding_scope = day");
+        let rounding = config.rounding().unwrap();
+
+        assert_eq!(15, rounding.minutes);
+        assert_eq!(RoundingMode::Up, rounding.mode);
+        assert_eq!(RoundingScope::Day, rounding.scope);
+        assert_eq!(RoundingApply::Report, rounding.apply);
+    }
+
+    #[test]
+    fn test_parse_duration_format() {
+        let config = Config::parse("duration_format.report = decimal\ndecimal_places = 1");
+
+        assert_eq!(DurationFormat::Clock, config.duration_format("term"));
+        assert_eq!(DurationFormat::Decimal(1), config.duration_format("report"));
+
+        let config = Config::parse("duration_format = decimal\nduration_format.file = clock");
+        assert_eq!(DurationFormat::Decimal(2), config.duration_format("term"));
+        assert_eq!(DurationFormat::Clock, config.duration_format("file"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Ok(()), Config::default().validate());
+        assert_eq!(
+            Err("Unknown precision hours".to_string()),
+            Config::parse("precision = hours").validate()
+        );
+        assert!(Config::parse("rounding = 7").validate().is_err());
+        assert!(Config::parse("break_deduction = yes").validate().is_err());
+        assert!(Config::parse("daily_target = 8h").validate().is_err());
+        assert!(Config::parse("holidays = 24.12.").validate().is_err());
+        assert!(Config::parse("duration_format.report = float")
+            .validate()
+            .is_err());
+        assert!(Config::parse("date_format = %a %Q").validate().is_err());
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+
+        assert_eq!(None, config.name());
+        assert_eq!(Duration::from_secs(8 * 60 * 60), config.daily_target());
+        assert_eq!(Precision::Minutes, config.precision());
+    }
+}
@@ -3,6 +3,7 @@ use std::cmp::Ord;
 use std::time::Duration;
 
 use crate::block::Block;
+use crate::fmt;
 use crate::parse;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -29,44 +30,105 @@ impl Day {
         parse::parse_day(line)
     }
 
+    pub fn try_from_brf(line: &str) -> Result<Self, String> {
+        parse::try_parse_day(line)
+    }
+
     pub fn add_comment(&mut self, comment: &str) {
         self.comment = Some(comment.to_string());
     }
 
+    /// Adds the comment as a new line of the existing comment.
+    pub fn append_comment(&mut self, comment: &str) {
+        self.comment = match self.comment.take() {
+            Some(c) => Some(format!("{}\n{}", c, comment)),
+            None => Some(comment.to_string()),
+        };
+    }
+
     pub fn clear_comment(&mut self) {
         self.comment = None;
     }
 
+    /// Like `duration`, but includes the time elapsed in a running block.
+    pub fn live_duration(&self, now: &DateTime<Local>) -> Duration {
+        self.blocks
+            .iter()
+            .map(|b| b.live_duration(now))
+            .sum::<Duration>()
+    }
+
     pub fn add_block(&mut self, to_add: &Block) {
-        let mut block = Block {
-            from: to_add.from,
-            to: to_add.to,
-        };
+        let mut block = to_add.clone();
 
-        // Ignore when there are existing blocks containing the new block
-        if self.blocks.iter().any(|b| b.contains(&block)) {
-            return;
-        }
+        // Blocks of another project aren't merged, they make room for the new
+        // block instead. Blocks without a project merge like before.
+        let (others, same) = self
+            .blocks
+            .drain(..)
+            .partition::<Vec<_>, _>(|b| is_other_project(b, &block));
+        let mut rest = Day {
+            date: self.date,
+            blocks: others,
+            comment: None,
+        };
+        rest.cut_block(&block);
+        self.blocks = same;
 
-        // Handle overlaps
-        if let Some(b) = self.blocks.iter().find(|b| block.contains_dt(b.to)) {
-            if b.from <= block.from {
-                block.from = b.from;
+        // Existing blocks containing the new block only take over its note
+        if let Some(b) = self.blocks.iter_mut().find(|b| b.contains(&block)) {
+            if block.note.is_some() {
+                b.note = block.note;
             }
-        };
-        self.blocks.retain(|b| !block.contains(b));
+        } else {
+            // Handle overlaps, merged blocks keep the first note
+            if let Some(b) = self.blocks.iter().find(|b| block.contains_dt(b.to)) {
+                if b.from <= block.from {
+                    block.from = b.from;
+                }
+            };
+            if block.note.is_none() {
+                block.note = self.contained_note(&block);
+            }
+            if block.project.is_none() {
+                block.project = self.contained_project(&block);
+            }
+            self.blocks.retain(|b| !block.contains(b));
 
-        if let Some(b) = self.blocks.iter().find(|b| block.contains_dt(b.from)) {
-            if b.to >= block.to {
-                block.to = b.to;
+            if let Some(b) = self.blocks.iter().find(|b| block.contains_dt(b.from)) {
+                if b.to >= block.to {
+                    block.to = b.to;
+                }
+            };
+            if block.note.is_none() {
+                block.note = self.contained_note(&block);
             }
-        };
-        self.blocks.retain(|b| !block.contains(b));
+            if block.project.is_none() {
+                block.project = self.contained_project(&block);
+            }
+            self.blocks.retain(|b| !block.contains(b));
 
-        self.blocks.push(block);
+            self.blocks.push(block);
+        }
+
+        self.blocks.append(&mut rest.blocks);
         self.blocks.sort();
     }
 
+    fn contained_note(&self, block: &Block) -> Option<String> {
+        self.blocks
+            .iter()
+            .filter(|b| block.contains(b))
+            .find_map(|b| b.note.clone())
+    }
+
+    fn contained_project(&self, block: &Block) -> Option<String> {
+        self.blocks
+            .iter()
+            .filter(|b| block.contains(b))
+            .find_map(|b| b.project.clone())
+    }
+
     pub fn remove_block(&mut self, to_remove: &Block) {
         self.blocks.retain(|b| !to_remove.contains(b));
 
@@ -78,7 +140,7 @@ impl Day {
         if let Some(b) = to_split {
             let new_block = Block {
                 from: to_remove.to,
-                to: b.to,
+                ..b.clone()
             };
             b.to = to_remove.from;
             self.add_block(&new_block);
@@ -96,10 +158,71 @@ impl Day {
         };
     }
 
+    /// Makes room for a block of another project. Unlike `remove_block`,
+    /// blocks that only touch the cut keep their full length.
+    fn cut_block(&mut self, to_cut: &Block) {
+        if to_cut.is_ongoing() {
+            return;
+        }
+
+        self.blocks.retain(|b| !to_cut.contains(b));
+
+        let mut split = vec![];
+        for b in self.blocks.iter_mut() {
+            if b.strictly_contains(to_cut) {
+                split.push(Block {
+                    from: to_cut.to,
+                    ..b.clone()
+                });
+                b.to = to_cut.from;
+            } else if b.from < to_cut.from && b.contains_dt(to_cut.from) {
+                b.to = to_cut.from;
+            } else if b.to > to_cut.to && b.contains_dt(to_cut.to) {
+                b.from = to_cut.to;
+            }
+        }
+        self.blocks.append(&mut split);
+    }
+
+    /// Closes the running block at `now` and starts a new one for `project`.
+    /// A running block that starts after `now` can't be switched.
+    pub fn switch_block(
+        &mut self,
+        now: &DateTime<Local>,
+        project: Option<String>,
+    ) -> Result<(), String> {
+        if let Some(b) = self.blocks.iter().find(|b| b.is_ongoing() && b.from > *now) {
+            return Err(format!(
+                "The running block only starts at {}",
+                fmt::format_block_date(&b.from)
+            ));
+        }
+
+        // A block started at the same minute is replaced
+        self.blocks.retain(|b| !(b.is_ongoing() && b.from == *now));
+
+        if let Some(b) = self.blocks.iter_mut().find(|b| b.is_ongoing()) {
+            b.to = *now;
+        }
+
+        // The new block starts where the closed one ends, so it's added as
+        // is instead of being merged
+        let mut block = Block::new(*now, *now);
+        block.project = project;
+        self.blocks.push(block);
+        self.blocks.sort();
+
+        Ok(())
+    }
+
     pub fn find_ongoing_block(&self) -> Option<&Block> {
         self.blocks.iter().find(|b| b.is_ongoing())
     }
 
+    pub fn find_running_block(&self, now: &DateTime<Local>) -> Option<&Block> {
+        self.blocks.iter().find(|b| b.is_running(now))
+    }
+
     pub fn is_empty(&self) -> bool {
         self.blocks.is_empty() && self.comment.is_none()
     }
@@ -109,6 +232,11 @@ impl Day {
     }
 }
 
+/// Whether the blocks are tagged with different projects.
+fn is_other_project(block: &Block, other: &Block) -> bool {
+    block.project.is_some() && other.project.is_some() && block.project != other.project
+}
+
 impl Ord for Day {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.date.cmp(&other.date)
@@ -117,14 +245,13 @@ impl Ord for Day {
 
 impl PartialOrd for Day {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.date.cmp(&other.date))
+        Some(self.cmp(other))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::fmt;
     use crate::parse;
 
     #[test]
@@ -204,7 +331,7 @@ mod tests {
         let mut day = parse::parse_day("12.02.20  12:00-14:00  Total: 02:00");
         let block = parse::parse_block(&day.date, "12:00-14:00");
         day.remove_block(&block);
-        assert_eq!(true, day.is_empty());
+        assert!(day.is_empty());
     }
 
     #[test]
@@ -236,4 +363,95 @@ mod tests {
         assert_eq!(1, day.blocks.len());
         assert_eq!("08:00", fmt::format_duration(&day.duration()));
     }
+
+    #[test]
+    fn test_block_notes() {
+        let mut day =
+            parse::parse_day(r#"12.02.20  08:00-10:00 "standup"  11:00-12:00  Total: 03:00"#);
+        day.add_block(&parse::parse_block(&day.date, "09:00-11:30"));
+        assert_eq!(Some("standup".to_string()), day.blocks[0].note);
+
+        let mut block = parse::parse_block(&day.date, "09:00-10:00");
+        block.note = Some("review".to_string());
+        day.add_block(&block);
+        assert_eq!(
+            vec![r#"08:00-12:00 "review""#],
+            day.blocks
+                .iter()
+                .map(|b| fmt::format_block(b, fmt::OutputMode::File, fmt::Precision::Minutes))
+                .collect::<Vec<_>>()
+        );
+
+        day.remove_block(&parse::parse_block(&day.date, "10:00-11:00"));
+        assert!(day.blocks.iter().all(|b| b.note == block.note));
+    }
+
+    #[test]
+    fn test_adding_blocks_of_other_projects() {
+        let mut day = parse::parse_day("12.02.20  08:00-12:00@acme  Total: 04:00");
+        day.add_block(&parse::parse_block(&day.date, "12:00-14:00@initech"));
+        assert_eq!(2, day.blocks.len());
+
+        day.add_block(&parse::parse_block(&day.date, "11:00-13:00@acme"));
+        assert_eq!(
+            vec!["08:00-13:00@acme", "13:00-14:00@initech"],
+            day.blocks
+                .iter()
+                .map(|b| fmt::format_block(b, fmt::OutputMode::File, fmt::Precision::Minutes))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_adding_blocks_without_project() {
+        let mut day = parse::parse_day("12.02.20  08:00-12:00@acme  Total: 04:00");
+        day.add_block(&parse::parse_block(&day.date, "11:00-14:00"));
+
+        assert_eq!(
+            vec!["08:00-14:00@acme"],
+            day.blocks
+                .iter()
+                .map(|b| fmt::format_block(b, fmt::OutputMode::File, fmt::Precision::Minutes))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_switching_blocks() {
+        let mut day = parse::parse_day("12.02.20  08:00-08:00@acme  Total: 00:00");
+        let now = Local.ymd(2020, 2, 12).and_hms(10, 30, 0);
+        day.switch_block(&now, Some("initech".to_string())).unwrap();
+
+        assert_eq!(2, day.blocks.len());
+        assert_eq!("02:30", fmt::format_duration(&day.duration()));
+        assert_eq!(
+            Some("initech"),
+            day.find_ongoing_block().unwrap().project.as_deref()
+        );
+
+        // Switching again in the same minute replaces the new block
+        day.switch_block(&now, None).unwrap();
+        assert_eq!(2, day.blocks.len());
+        assert_eq!(None, day.find_ongoing_block().unwrap().project);
+    }
+
+    #[test]
+    fn test_switching_future_block() {
+        let mut day = parse::parse_day("12.02.20  11:00-11:00@acme  Total: 00:00");
+        let now = Local.ymd(2020, 2, 12).and_hms(10, 30, 0);
+
+        assert!(day.switch_block(&now, None).is_err());
+        assert_eq!(1, day.blocks.len());
+        assert!(day.find_ongoing_block().is_some());
+    }
+
+    #[test]
+    fn test_switching_without_running_block() {
+        let mut day = Day::from_date(&NaiveDate::from_ymd(2020, 2, 12));
+        let now = Local.ymd(2020, 2, 12).and_hms(10, 30, 0);
+        day.switch_block(&now, None).unwrap();
+
+        assert_eq!(1, day.blocks.len());
+        assert!(day.find_ongoing_block().is_some());
+    }
 }
@@ -1,21 +1,46 @@
 use crate::parse::DATE_FORMAT;
+use crate::parse::PROJECT_SEP;
 use chrono::prelude::*;
 
 use colored::*;
 use std::time::Duration;
 
 use crate::block::Block;
-use crate::clock::PunchCard;
+use crate::card::PunchCard;
+use crate::compliance;
 use crate::day::Day;
+use crate::locale::Locale;
+use crate::month;
 use crate::month::Month;
+use crate::parse;
 
 pub const BLOCK_FORMAT: &str = "%H:%M";
+pub const PRECISE_BLOCK_FORMAT: &str = "%H:%M:%S";
 pub const BLOCK_SEP: &str = "-";
 pub const EMPTY_BLOCK: &str = "           ";
 pub const EMPTY_HALF_BLOCK: &str = "     ";
+pub const PRECISE_EMPTY_BLOCK: &str = "                 ";
+pub const PRECISE_EMPTY_HALF_BLOCK: &str = "        ";
 pub const SPACER: &str = "   ";
 pub const NEWLINE: &str = "\n";
-pub const TERM_DATE_FORMAT: &str = "%a   %d.%m.%y";
+pub const PROVISIONAL_MARK: &str = "*";
+pub const COMMENT_LINE_SEP: &str = "; ";
+
+/// Whether totals are shown to the minute or to the second. Times with
+/// seconds are always kept.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Precision {
+    Minutes,
+    Seconds,
+}
+
+/// Whether durations are shown like `08:30` or in decimal hours like
+/// `8.50`, with the given number of decimal places.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DurationFormat {
+    Clock,
+    Decimal(usize),
+}
 
 #[derive(Copy, Clone)]
 pub enum OutputMode {
@@ -23,7 +48,18 @@ pub enum OutputMode {
     File,
 }
 
+impl OutputMode {
+    /// Name of the mode in config keys like `duration_format.term`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputMode::Term => "term",
+            OutputMode::File => "file",
+        }
+    }
+}
+
 pub fn format_month(month: &Month, card: &PunchCard, mode: OutputMode) -> String {
+    let now = Local::now();
     let pad_blocks = month.max_num_blocks_in_day();
     let days = match mode {
         OutputMode::Term => month.full_sorted_days(),
@@ -31,40 +67,89 @@ pub fn format_month(month: &Month, card: &PunchCard, mode: OutputMode) -> String
     }
     .iter()
     .enumerate()
-    .map(|(index, d)| format_day(d, index, pad_blocks, card, mode))
+    .map(|(index, d)| format_day(d, index, pad_blocks, card, mode, &now))
     .collect::<Vec<_>>()
     .join(NEWLINE);
 
-    let total = format!("Total: {}", format_duration(&month.duration()));
+    let format = card.config.duration_format(mode.name());
+    let deducted = match mode {
+        OutputMode::Term if card.config.break_deduction() => compliance::month_missing_break(month),
+        _ => Duration::ZERO,
+    };
+    let rounded = card.config.rounding().map(|r| r.month_duration(month));
+    let total = format!(
+        "{}{}",
+        format_total(
+            &(month.duration() - deducted),
+            &(month.live_duration(&now) - deducted),
+            rounded.as_ref(),
+            card.config.precision(),
+            format,
+            mode,
+        ),
+        format_deduction(&deducted, format)
+    );
+    let provisional = match mode {
+        OutputMode::Term if month.live_duration(&now) != month.duration() => format!(
+            "{}{} provisional, includes the running block",
+            NEWLINE, PROVISIONAL_MARK
+        ),
+        _ => "".to_string(),
+    };
+    let violations = match mode {
+        OutputMode::Term if !card.violations().is_empty() => format!(
+            "{}Violations:{}{}",
+            NEWLINE.repeat(2),
+            NEWLINE,
+            card.violations()
+                .iter()
+                .map(|v| format!("{}{}", SPACER, v.format(&card.locale, format)))
+                .collect::<Vec<_>>()
+                .join(NEWLINE)
+        )
+        .red()
+        .to_string(),
+        _ => "".to_string(),
+    };
+
+    let title = match mode {
+        OutputMode::Term => card.locale.month_title(month),
+        OutputMode::File => month.title(),
+    };
+
     format!(
-        "{}{}{}{}{}",
-        month.title(),
+        "{}{}{}{}{}{}{}",
+        title,
         NEWLINE.repeat(2),
         days,
         NEWLINE.repeat(2),
-        total
+        total,
+        provisional,
+        violations
     )
 }
 
-fn format_date(date: &NaiveDate, mode: OutputMode, index: usize) -> String {
+pub fn format_date(date: &NaiveDate, mode: OutputMode, index: usize, locale: &Locale) -> String {
     let monday_is_first_day_in_month = index == 0;
     match (mode, date.weekday(), monday_is_first_day_in_month) {
         (OutputMode::Term, Weekday::Mon, false) => {
-            format!("{}{}", NEWLINE, date.format(TERM_DATE_FORMAT))
+            format!("{}{}", NEWLINE, locale.format_date(date))
         }
-        (OutputMode::Term, _, _) => date.format(TERM_DATE_FORMAT).to_string(),
+        (OutputMode::Term, _, _) => locale.format_date(date),
         _ => date.format(DATE_FORMAT).to_string(),
     }
 }
 
-fn format_day(
+pub fn format_day(
     day: &Day,
     index: usize,
     pad_blocks: usize,
     card: &PunchCard,
     mode: OutputMode,
+    now: &DateTime<Local>,
 ) -> String {
-    let date = format_date(&day.date, mode, index);
+    let date = format_date(&day.date, mode, index, &card.locale);
+    let precision = card.config.precision();
 
     let blocks = if day.blocks.is_empty() {
         "".to_string()
@@ -74,23 +159,51 @@ fn format_day(
             SPACER,
             day.blocks
                 .iter()
-                .map(|b| format_block(b, mode))
+                .map(|b| format_block(b, mode, precision))
                 .collect::<Vec<_>>()
                 .join(SPACER)
         )
     };
 
+    let empty_block = match precision {
+        Precision::Minutes => EMPTY_BLOCK,
+        Precision::Seconds => PRECISE_EMPTY_BLOCK,
+    };
     let padding = if day.blocks.len() < pad_blocks {
-        format!("{}{}", SPACER, EMPTY_BLOCK).repeat(pad_blocks - day.blocks.len())
+        format!("{}{}", SPACER, empty_block).repeat(pad_blocks - day.blocks.len())
     } else {
         "".to_string()
     };
 
-    let total = format!("Total: {}", format_duration(&day.duration()));
+    let format = card.config.duration_format(mode.name());
+    let deducted = match mode {
+        OutputMode::Term if card.config.break_deduction() => compliance::missing_break(day),
+        _ => Duration::ZERO,
+    };
+    let rounded = card.config.rounding().map(|r| r.day_duration(day));
+    let total = format!(
+        "{}{}",
+        format_total(
+            &(day.duration() - deducted),
+            &(day.live_duration(now) - deducted),
+            rounded.as_ref(),
+            card.config.precision(),
+            format,
+            mode,
+        ),
+        format_deduction(&deducted, format)
+    );
 
-    let comment = match &day.comment {
-        Some(c) => format!("{}{}", SPACER, c),
-        None => "".to_string(),
+    let comment = match (&day.comment, mode) {
+        (Some(c), OutputMode::File) => format!("{}{}", SPACER, parse::quote_comment(c)),
+        (Some(c), OutputMode::Term) => {
+            format!(
+                "{}{}",
+                SPACER,
+                c.lines().collect::<Vec<_>>().join(COMMENT_LINE_SEP)
+            )
+        }
+        (None, _) => "".to_string(),
     };
 
     let output = format!(
@@ -100,30 +213,119 @@ fn format_day(
 
     match mode {
         OutputMode::File => output,
+        OutputMode::Term => highlight(output, day, card, now),
+    }
+}
+
+/// Colours the terminal output of a day with the theme colour of the first
+/// that applies: modified, ongoing, selected, holiday or weekend.
+pub fn highlight(output: String, day: &Day, card: &PunchCard, now: &DateTime<Local>) -> String {
+    let theme = &card.theme;
+    let color = if card.was_modified(&day.date) {
+        theme.modified
+    } else if day.blocks.iter().any(|b| b.is_running(now)) && theme.ongoing.is_some() {
+        theme.ongoing
+    } else if card.was_selected(&day.date) {
+        theme.selected
+    } else if card.is_holiday(&day.date) {
+        theme.holiday
+    } else if !month::is_workday(&day.date) {
+        theme.weekend
+    } else {
+        None
+    };
+
+    theme.paint(output, color)
+}
+
+/// The BRF file only ever contains raw totals. The terminal includes the
+/// time elapsed in a running block, marked as provisional, and additionally
+/// shows the rounded total in parentheses.
+fn format_total(
+    raw: &Duration,
+    live: &Duration,
+    rounded: Option<&Duration>,
+    precision: Precision,
+    format: DurationFormat,
+    mode: OutputMode,
+) -> String {
+    match mode {
+        OutputMode::File => format!("Total: {}", format_duration_as(raw, precision, format)),
         OutputMode::Term => {
-            if card.was_modified(&day.date) {
-                output.truecolor(255, 146, 209).to_string()
-            } else if card.was_selected(&day.date) {
-                output.truecolor(201, 169, 250).to_string()
-            } else {
-                output
+            let mark = if live != raw { PROVISIONAL_MARK } else { "" };
+            match rounded {
+                Some(r) => format!(
+                    "Total: {}{} ({})",
+                    format_duration_as(live, precision, format),
+                    mark,
+                    format_duration_as(r, Precision::Minutes, format)
+                ),
+                None => format!(
+                    "Total: {}{}",
+                    format_duration_as(live, precision, format),
+                    mark
+                ),
             }
         }
     }
 }
 
+/// Notes the break deducted from a total, if any.
+fn format_deduction(deducted: &Duration, format: DurationFormat) -> String {
+    if deducted.is_zero() {
+        return "".to_string();
+    }
+
+    format!(
+        " -{} break",
+        format_duration_as(deducted, Precision::Minutes, format)
+    )
+}
+
+/// Formats the time as `HH:MM`, or as `HH:MM:SS` if it has seconds.
 pub fn format_block_date(dt: &DateTime<Local>) -> String {
-    dt.format(BLOCK_FORMAT).to_string()
+    match dt.second() {
+        0 => dt.format(BLOCK_FORMAT).to_string(),
+        _ => dt.format(PRECISE_BLOCK_FORMAT).to_string(),
+    }
 }
 
-fn format_block(block: &Block, mode: OutputMode) -> String {
-    let from_str = format_block_date(&block.from);
+/// Formats the time at the given precision, so all times have the same
+/// width.
+pub fn format_block_date_as(dt: &DateTime<Local>, precision: Precision) -> String {
+    match precision {
+        Precision::Minutes => dt.format(BLOCK_FORMAT).to_string(),
+        Precision::Seconds => dt.format(PRECISE_BLOCK_FORMAT).to_string(),
+    }
+}
+
+/// Formats the block at the given precision. Files keep the seconds of times
+/// that have them.
+pub fn format_block(block: &Block, mode: OutputMode, precision: Precision) -> String {
+    let format_half = |dt: &DateTime<Local>| match (mode, precision) {
+        (OutputMode::File, Precision::Minutes) => format_block_date(dt),
+        _ => format_block_date_as(dt, precision),
+    };
+    let empty_half_block = match precision {
+        Precision::Minutes => EMPTY_HALF_BLOCK,
+        Precision::Seconds => PRECISE_EMPTY_HALF_BLOCK,
+    };
+
+    let from_str = format_half(&block.from);
     let to_str = match (block.is_ongoing(), mode) {
-        (true, OutputMode::Term) => EMPTY_HALF_BLOCK.to_string(),
-        _ => format_block_date(&block.to),
+        (true, OutputMode::Term) => empty_half_block.to_string(),
+        _ => format_half(&block.to),
     };
 
-    format!("{}{}{}", from_str, BLOCK_SEP, to_str)
+    let mut output = format!("{}{}{}", from_str, BLOCK_SEP, to_str);
+    if let Some(p) = &block.project {
+        output = format!("{}{}{}", output, PROJECT_SEP, p);
+    }
+    if let Some(n) = &block.note {
+        output = format!("{} {}", output, parse::quote_note(n));
+    }
+
+    output
 }
 
 pub fn format_duration(duration: &Duration) -> String {
@@ -133,48 +335,182 @@ pub fn format_duration(duration: &Duration) -> String {
     format!("{:02}:{:02}", hours, remaining_minutes)
 }
 
+pub fn format_precise_duration(duration: &Duration, precision: Precision) -> String {
+    match precision {
+        Precision::Minutes => format_duration(duration),
+        Precision::Seconds => format!(
+            "{}:{:02}",
+            format_duration(duration),
+            duration.as_secs() % 60
+        ),
+    }
+}
+
+pub fn format_duration_as(
+    duration: &Duration,
+    precision: Precision,
+    format: DurationFormat,
+) -> String {
+    match format {
+        DurationFormat::Clock => format_precise_duration(duration, precision),
+        DurationFormat::Decimal(places) => format_decimal_duration(duration, places),
+    }
+}
+
+/// Hours with the given number of decimal places, e.g. `8.50`.
+pub fn format_decimal_duration(duration: &Duration, places: usize) -> String {
+    format!("{:.*}", places, duration.as_secs_f64() / 3600.0)
+}
+
+/// Formats a duration in the terminal format of the card.
+pub fn format_term_duration(duration: &Duration, card: &PunchCard) -> String {
+    format_duration_as(
+        duration,
+        Precision::Minutes,
+        card.config.duration_format(OutputMode::Term.name()),
+    )
+}
+
+/// Lists the day comments and block notes of the month, e.g. for writing
+/// status reports. Lines of multiline notes are indented.
+pub fn format_notes(month: &Month, locale: &Locale) -> String {
+    let indent = |text: &str| {
+        text.lines()
+            .collect::<Vec<_>>()
+            .join(&format!("{}{}", NEWLINE, SPACER.repeat(2)))
+    };
+
+    let mut lines = vec![];
+    for day in month.sorted_days() {
+        let date = locale.format_date(&day.date);
+        if let Some(c) = &day.comment {
+            lines.push(format!("{}{}{}", date, SPACER, indent(c)));
+        }
+
+        for block in day.blocks.iter() {
+            if let Some(n) = &block.note {
+                let times = Block {
+                    note: None,
+                    ..block.clone()
+                };
+                lines.push(format!(
+                    "{}{}{}{}{}",
+                    date,
+                    SPACER,
+                    format_block(&times, OutputMode::File, Precision::Minutes),
+                    SPACER,
+                    indent(n)
+                ));
+            }
+        }
+    }
+
+    lines.join(NEWLINE)
+}
+
+/// Difference between worked and expected time, e.g. `+01:30` or `-00:45`.
+pub fn format_balance(worked: &Duration, target: &Duration, format: DurationFormat) -> String {
+    let (sign, difference) = if worked >= target {
+        ("+", *worked - *target)
+    } else {
+        ("-", *target - *worked)
+    };
+
+    format!(
+        "{}{}",
+        sign,
+        format_duration_as(&difference, Precision::Minutes, format)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Config;
+
+    fn card() -> PunchCard {
+        let dir = std::env::temp_dir().join(format!("punch-fmt-{}", std::process::id()));
+        PunchCard::with_config(dir.to_str().unwrap(), Config::default()).unwrap()
+    }
 
     #[test]
     fn test_format_block() {
-        let from = Local.ymd(2022, 01, 01).and_hms(8, 15, 0);
-        let to = Local.ymd(2022, 01, 01).and_hms(14, 45, 0);
-        let block = Block { from, to };
-        assert_eq!("08:15-14:45", format_block(&block, OutputMode::File));
+        let from = Local.ymd(2022, 1, 1).and_hms(8, 15, 0);
+        let to = Local.ymd(2022, 1, 1).and_hms(14, 45, 0);
+        let block = Block::new(from, to);
+        assert_eq!(
+            "08:15-14:45",
+            format_block(&block, OutputMode::File, Precision::Minutes)
+        );
+    }
+
+    #[test]
+    fn test_format_block_with_note() {
+        let contents = r#"01.02.22  09:00-10:30@acme "standup \"daily\""  Total: 01:30"#;
+        let day = Day::from_brf(contents);
+        let formatted = format_block(&day.blocks[0], OutputMode::File, Precision::Minutes);
+
+        assert_eq!(r#"09:00-10:30@acme "standup \"daily\"""#, formatted);
+        assert_eq!(
+            day,
+            Day::from_brf(&format!("01.02.22  {}  Total: 01:30", formatted))
+        );
+    }
+
+    #[test]
+    fn test_format_notes() {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-12:00 "standup\nreview"   13:00-14:00   Total: 05:00   sick
+            02.02.22   08:00-12:00   Total: 04:00
+
+            Total: 09:00
+        "#;
+        let month = Month::from_brf(contents, 2022, 2);
+
+        assert_eq!(
+            "Tue   01.02.22   sick\nTue   01.02.22   08:00-12:00   standup\n      review",
+            format_notes(&month, &Locale::default())
+        );
     }
 
     #[test]
     fn test_format_ongoing_block() {
-        let from = Local.ymd(2022, 01, 01).and_hms(8, 15, 0);
-        let to = Local.ymd(2022, 01, 01).and_hms(8, 15, 0);
-        let block = Block { from, to };
-        assert_eq!("08:15-08:15", format_block(&block, OutputMode::File));
-        assert_eq!("08:15-     ", format_block(&block, OutputMode::Term));
+        let from = Local.ymd(2022, 1, 1).and_hms(8, 15, 0);
+        let to = Local.ymd(2022, 1, 1).and_hms(8, 15, 0);
+        let block = Block::new(from, to);
+        assert_eq!(
+            "08:15-08:15",
+            format_block(&block, OutputMode::File, Precision::Minutes)
+        );
+        assert_eq!(
+            "08:15-     ",
+            format_block(&block, OutputMode::Term, Precision::Minutes)
+        );
     }
 
     #[test]
     fn test_format_day() {
-        let card = PunchCard::new();
+        let card = card();
         let day =
             Day::from_brf("  04.05.20    08:30-12:00    12:30-17:30             Total: 08:30");
 
         assert_eq!(
             "04.05.20   08:30-12:00   12:30-17:30   Total: 08:30",
-            format_day(&day, 0, 0, &card, OutputMode::File)
+            format_day(&day, 0, 0, &card, OutputMode::File, &Local::now())
         );
     }
 
     #[test]
     fn test_format_day_with_padding() {
-        let card = PunchCard::new();
+        let card = card();
         let day =
             Day::from_brf("  04.05.20    08:30-12:00    12:30-17:30             Total: 08:30");
 
         assert_eq!(
             "04.05.20   08:30-12:00   12:30-17:30                                             Total: 08:30",
-            format_day(&day, 0, 5, &card, OutputMode::File)
+            format_day(&day, 0, 5, &card, OutputMode::File, &Local::now())
         );
     }
 
@@ -184,4 +520,157 @@ mod tests {
         let actual = format_duration(&duration);
         assert_eq!("08:30", actual);
     }
+
+    #[test]
+    fn test_format_deduction() {
+        let half_hour = Duration::from_secs(30 * 60);
+        assert_eq!("", format_deduction(&Duration::ZERO, DurationFormat::Clock));
+        assert_eq!(
+            " -00:30 break",
+            format_deduction(&half_hour, DurationFormat::Clock)
+        );
+        assert_eq!(
+            " -0.50 break",
+            format_deduction(&half_hour, DurationFormat::Decimal(2))
+        );
+    }
+
+    #[test]
+    fn test_format_balance() {
+        let eight = Duration::new(28800, 0);
+        let nine = Duration::new(32400, 0);
+        assert_eq!(
+            "+01:00",
+            format_balance(&nine, &eight, DurationFormat::Clock)
+        );
+        assert_eq!(
+            "-01:00",
+            format_balance(&eight, &nine, DurationFormat::Clock)
+        );
+        assert_eq!(
+            "+00:00",
+            format_balance(&eight, &eight, DurationFormat::Clock)
+        );
+        assert_eq!(
+            "-1.0",
+            format_balance(&eight, &nine, DurationFormat::Decimal(1))
+        );
+    }
+
+    #[test]
+    fn test_format_total() {
+        let raw = Duration::new(29220, 0);
+        let live = Duration::new(30000, 0);
+        let rounded = Duration::new(29700, 0);
+        assert_eq!(
+            "Total: 08:07",
+            format_total(
+                &raw,
+                &raw,
+                None,
+                Precision::Minutes,
+                DurationFormat::Clock,
+                OutputMode::Term
+            )
+        );
+        assert_eq!(
+            "Total: 08:07",
+            format_total(
+                &raw,
+                &live,
+                Some(&rounded),
+                Precision::Minutes,
+                DurationFormat::Clock,
+                OutputMode::File
+            )
+        );
+        assert_eq!(
+            "Total: 08:07 (08:15)",
+            format_total(
+                &raw,
+                &raw,
+                Some(&rounded),
+                Precision::Minutes,
+                DurationFormat::Clock,
+                OutputMode::Term
+            )
+        );
+        assert_eq!(
+            "Total: 08:20*",
+            format_total(
+                &raw,
+                &live,
+                None,
+                Precision::Minutes,
+                DurationFormat::Clock,
+                OutputMode::Term
+            )
+        );
+        assert_eq!(
+            "Total: 08:07:00",
+            format_total(
+                &raw,
+                &raw,
+                None,
+                Precision::Seconds,
+                DurationFormat::Clock,
+                OutputMode::File
+            )
+        );
+        assert_eq!(
+            "Total: 8.12 (8.25)",
+            format_total(
+                &raw,
+                &raw,
+                Some(&rounded),
+                Precision::Seconds,
+                DurationFormat::Decimal(2),
+                OutputMode::Term
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_precise_block() {
+        let from = Local.ymd(2022, 1, 1).and_hms(8, 15, 30);
+        let to = Local.ymd(2022, 1, 1).and_hms(8, 16, 0);
+        let block = Block::new(from, to);
+        assert_eq!(
+            "08:15:30-08:16",
+            format_block(&block, OutputMode::File, Precision::Minutes)
+        );
+        assert_eq!(
+            "08:15-08:16",
+            format_block(&block, OutputMode::Term, Precision::Minutes)
+        );
+        assert_eq!(
+            "08:15:30-08:16:00",
+            format_block(&block, OutputMode::Term, Precision::Seconds)
+        );
+
+        let ongoing = Block::new(to, to);
+        assert_eq!(
+            format_block(&block, OutputMode::Term, Precision::Seconds).len(),
+            format_block(&ongoing, OutputMode::Term, Precision::Seconds).len()
+        );
+        assert_eq!(
+            format_block(&block, OutputMode::Term, Precision::Seconds).len(),
+            PRECISE_EMPTY_BLOCK.len()
+        );
+    }
+
+    #[test]
+    fn test_format_block_with_project() {
+        let from = Local.ymd(2022, 1, 1).and_hms(8, 15, 0);
+        let mut block = Block::new(from, from);
+        block.project = Some("acme".to_string());
+        assert_eq!(
+            "08:15-08:15@acme",
+            format_block(&block, OutputMode::File, Precision::Minutes)
+        );
+        assert_eq!(
+            "08:15-     @acme",
+            format_block(&block, OutputMode::Term, Precision::Minutes)
+        );
+    }
 }
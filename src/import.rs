@@ -0,0 +1,199 @@
+use punch::card::PunchCard;
+use punch::config::CONFIG_FILE_NAME;
+use punch::fmt;
+use punch::fmt::OutputMode;
+use punch::parse;
+use punch::store::parse_brf_file_name;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub const LEGACY_CONFIG_FILE_NAME: &str = ".punchrc";
+
+/// Imports a hours directory of the original Ruby punch. BRF files at the top
+/// level end up in the hours directory, BRF files in subdirectories end up in
+/// the card of the same name. Card names and the user name are taken over
+/// from the legacy `.punchrc`.
+pub fn import_hours(legacy_dir: &Path, card: &PunchCard, dry_run: bool) {
+    let hours_dir = Path::new(card.hours_dir_path());
+    let mut cards = import_dir(legacy_dir, hours_dir, card, dry_run);
+
+    let legacy_config =
+        fs::read_to_string(legacy_dir.join(LEGACY_CONFIG_FILE_NAME)).unwrap_or_default();
+    let name = parse_legacy_name(&legacy_config);
+    for c in parse_legacy_cards(&legacy_config) {
+        if !cards.contains(&c) {
+            cards.push(c);
+        }
+    }
+    cards.sort();
+
+    let mut config = vec![];
+    if let Some(n) = name {
+        config.push(format!("name = {}", n));
+    }
+    if !cards.is_empty() {
+        config.push(format!("cards = {}", cards.join(", ")));
+    }
+    if config.is_empty() {
+        return;
+    }
+
+    let config = config.join(fmt::NEWLINE);
+    let config_path = hours_dir.join(CONFIG_FILE_NAME);
+    if config_path.exists() || dry_run {
+        println!(
+            "Add to {}:{}{}",
+            config_path.display(),
+            fmt::NEWLINE,
+            config
+        );
+    } else {
+        fs::write(&config_path, format!("{}{}", config, fmt::NEWLINE))
+            .expect("Could not write config");
+        println!("Wrote {}", config_path.display());
+    }
+}
+
+/// Imports all BRF files in `src` to `dest` and the ones in subdirectories
+/// to the card directory of the same name. Returns the imported card names.
+fn import_dir(src: &Path, dest: &Path, card: &PunchCard, dry_run: bool) -> Vec<String> {
+    let mut cards = vec![];
+
+    for path in brf_dir_entries(src) {
+        if path.is_dir() {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let brf_files = brf_dir_entries(&path)
+                .into_iter()
+                .filter(|p| parse_brf_file_name(p).is_some())
+                .collect::<Vec<_>>();
+
+            if name.starts_with('.') || brf_files.is_empty() {
+                continue;
+            }
+
+            brf_files
+                .iter()
+                .for_each(|p| import_brf(p, &dest.join(&name), card, dry_run));
+            cards.push(name);
+        } else if parse_brf_file_name(&path).is_some() {
+            import_brf(&path, dest, card, dry_run);
+        }
+    }
+
+    cards
+}
+
+fn brf_dir_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries = fs::read_dir(dir)
+        .expect("Could not read legacy hours directory")
+        .map(|e| e.unwrap().path())
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+fn import_brf(path: &Path, dest: &Path, card: &PunchCard, dry_run: bool) {
+    let (year, month_number) = parse_brf_file_name(path).unwrap();
+    let target = dest.join(format!("{}-{}.txt", year, month_number));
+    if target.exists() {
+        println!(
+            "Skipping {}, {} already exists",
+            path.display(),
+            target.display()
+        );
+        return;
+    }
+
+    let contents = fs::read_to_string(path).expect("Could not read legacy BRF file");
+    let mut month = match parse::try_parse_month(&contents, year as u16, month_number as u8) {
+        Ok(month) => month,
+        Err(e) => {
+            println!("Skipping {}: {}", path.display(), e);
+            return;
+        }
+    };
+    println!("Importing {} to {}", path.display(), target.display());
+    if dry_run {
+        return;
+    }
+
+    month.cleanup();
+    fs::create_dir_all(dest).expect("Could not create hours directory");
+    fs::write(&target, fmt::format_month(&month, card, OutputMode::File))
+        .expect("Could not write file");
+}
+
+/// Extracts `config.name = "..."` from a legacy `.punchrc`.
+fn parse_legacy_name(punchrc: &str) -> Option<String> {
+    punchrc
+        .lines()
+        .map(|l| l.trim())
+        .filter_map(|l| l.strip_prefix("config.name"))
+        .filter_map(|l| l.trim().strip_prefix('='))
+        .map(|l| l.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        .find(|n| !n.is_empty())
+}
+
+/// Extracts the top level keys of the `config.cards = { ... }` hash in a
+/// legacy `.punchrc`, supporting both `acme: {` and `:acme => {`.
+fn parse_legacy_cards(punchrc: &str) -> Vec<String> {
+    let mut cards = vec![];
+    let mut depth = 0;
+    let mut in_cards = false;
+
+    for line in punchrc.lines().map(|l| l.trim()) {
+        if !in_cards {
+            if line.starts_with("config.cards") && line.contains('{') {
+                in_cards = true;
+                depth = 1;
+            }
+            continue;
+        }
+
+        if depth == 1 {
+            let key = match line.strip_prefix(':') {
+                Some(rest) => rest.split(|c: char| c.is_whitespace() || c == '=').next(),
+                None => line.split_once(':').map(|(k, _)| k),
+            };
+
+            if let Some(k) = key {
+                let k = k.trim().trim_matches(|c| c == '"' || c == '\'');
+                if !k.is_empty() && k.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    cards.push(k.to_string());
+                }
+            }
+        }
+
+        depth += line.matches('{').count();
+        depth -= line.matches('}').count().min(depth);
+        if depth == 0 {
+            break;
+        }
+    }
+
+    cards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUNCHRC: &str = r#"
+        config.name = "Jane Doe"
+        config.hours_folder = "~/Dropbox/hours"
+        config.cards = {
+          acme: {
+            title: "ACME Corp.",
+            hourly_pay: 100
+          },
+          :initech => { title: "Initech" }
+        }
+    "#;
+
+    #[test]
+    fn test_parse_legacy_config() {
+        assert_eq!(Some("Jane Doe".to_string()), parse_legacy_name(PUNCHRC));
+        assert_eq!(vec!["acme", "initech"], parse_legacy_cards(PUNCHRC));
+    }
+}
@@ -3,7 +3,6 @@ use chrono::prelude::*;
 use crate::block::Block;
 use crate::day::Day;
 use crate::fmt;
-use crate::fmt::BLOCK_FORMAT;
 use crate::fmt::BLOCK_SEP;
 use crate::parse;
 
@@ -24,41 +23,175 @@ pub fn next_month(my: MonthYear) -> MonthYear {
 }
 
 pub fn infer_month(input: &str, my: MonthYear) -> MonthYear {
-    let parts = input.split('.').collect::<Vec<_>>();
-    let m = parts
-        .get(0)
-        .map(|m| m.parse::<u32>().unwrap())
-        .expect("Could not parse month");
+    try_infer_month(input, my).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_infer_month(input: &str, my: MonthYear) -> Result<MonthYear, String> {
+    let invalid = || format!("Invalid month '{}'", input);
+    let parts = input
+        .split('.')
+        .map(|s| s.parse::<i32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (m, y) = match parts[..] {
+        [m] => (m, my.1),
+        [m, y] if y < 100 => (m, y + 2000),
+        [m, y] => (m, y),
+        _ => return Err(invalid()),
+    };
 
-    if parts.len() == 1 {
-        return (m, my.1);
+    if (1..=12).contains(&m) {
+        Ok((m as u32, y))
+    } else {
+        Err(invalid())
     }
+}
 
-    let mut y = parts
-        .get(1)
-        .map(|y| y.parse::<i32>().unwrap())
-        .expect("Could not parse year");
+pub fn infer_date(input: &str, date: &NaiveDate) -> NaiveDate {
+    try_infer_date(input, date).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_infer_date(input: &str, date: &NaiveDate) -> Result<NaiveDate, String> {
+    let invalid = || format!("Invalid date '{}'", input);
+    let parts = input
+        .split('.')
+        .map(|s| s.parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let inferred = match parts[..] {
+        [d, m, y] => NaiveDate::from_ymd_opt(y as i32, m, d),
+        [d, m] => NaiveDate::from_ymd_opt(date.year(), m, d),
+        [d] => NaiveDate::from_ymd_opt(date.year(), date.month(), d),
+        _ => None,
+    };
+    inferred.ok_or_else(invalid)
+}
 
-    if y < 100 {
-        y += 2000;
+/// Infers the dates of a `--day` value, which can be a single date, a range
+/// like `14..18` or `mon..wed`, or a comma separated list of both. Weekdays
+/// refer to the week of `date`. Ranges ending on an earlier day or weekday
+/// wrap into the next month or week.
+pub fn infer_dates(input: &str, date: &NaiveDate) -> Vec<NaiveDate> {
+    try_infer_dates(input, date).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_infer_dates(input: &str, date: &NaiveDate) -> Result<Vec<NaiveDate>, String> {
+    let mut dates = vec![];
+
+    for part in input.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        match part.split_once("..") {
+            Some((from, to_str)) => {
+                let from = infer_day(from, date)?;
+                let mut to = infer_day(to_str, &from)?;
+                if to < from && to_str.parse::<Weekday>().is_ok() {
+                    to += chrono::Duration::days(7);
+                } else if to < from && !to_str.contains('.') {
+                    let (m, y) = next_month((from.month(), from.year()));
+                    to = NaiveDate::from_ymd_opt(y, m, to.day())
+                        .ok_or_else(|| format!("Invalid date '{}'", to_str))?;
+                }
+                if to < from {
+                    return Err(format!("Date range {} ends before it starts", part));
+                }
+
+                let mut d = from;
+                while d <= to {
+                    dates.push(d);
+                    d = d.succ();
+                }
+            }
+            None => dates.push(infer_day(part, date)?),
+        }
     }
 
-    (m, y)
+    dates.sort();
+    dates.dedup();
+    Ok(dates)
 }
 
-pub fn infer_date(input: &str, date: &NaiveDate) -> NaiveDate {
-    let parts = input.split('.').collect::<Vec<_>>();
+fn infer_day(input: &str, date: &NaiveDate) -> Result<NaiveDate, String> {
+    match input.parse::<Weekday>() {
+        Ok(weekday) => {
+            let offset = weekday.num_days_from_monday() as i64
+                - date.weekday().num_days_from_monday() as i64;
+            Ok(*date + chrono::Duration::days(offset))
+        }
+        Err(_) => try_infer_date(input, date),
+    }
+}
 
-    let day = parts.get(0).map(|s| s.parse::<u32>().unwrap());
-    let month = parts.get(1).map(|s| s.parse::<u32>().unwrap());
-    let year = parts.get(2).map(|s| s.parse::<i32>().unwrap());
+/// The current time at the precision of BRF files.
+pub fn now_minute() -> DateTime<Local> {
+    Local::now()
+        .with_second(0)
+        .unwrap()
+        .with_nanosecond(0)
+        .unwrap()
+}
+
+/// Parses durations like `30m`, `2h`, `1h30` or `1h30m`.
+fn parse_duration(s: &str) -> Option<chrono::Duration> {
+    let (hours, minutes) = match s.split_once('h') {
+        Some((h, m)) => (h, m.strip_suffix('m').unwrap_or(m)),
+        None => ("0", s.strip_suffix('m').filter(|m| !m.is_empty())?),
+    };
 
-    match (year, month, day) {
-        (Some(y), Some(m), Some(d)) => NaiveDate::from_ymd(y, m, d),
-        (None, Some(m), Some(d)) => NaiveDate::from_ymd(date.year(), m, d),
-        (None, None, Some(d)) => NaiveDate::from_ymd(date.year(), date.month(), d),
-        _ => todo!("Improve error handling"),
+    let hours = hours.parse::<u32>().ok()? as i64;
+    let minutes = match minutes {
+        "" => 0,
+        m => m.parse::<u32>().ok()? as i64,
+    };
+
+    Some(chrono::Duration::minutes(hours * 60 + minutes))
+}
+
+/// Resolves `now` and the relative shorthands `now-30m`, `now+15m`, `-15m`
+/// (started 15 minutes ago) and `2h` (2 hours until now) to absolute times,
+/// which have to be on the day of `now`.
+fn resolve_relative(s: &str, now: &DateTime<Local>) -> Result<String, String> {
+    let shift = |d: chrono::Duration| {
+        let time = *now + d;
+        if time.date() == now.date() {
+            Ok(fmt::format_block_date(&time))
+        } else {
+            Err(format!("Block '{}' leaves the day", s))
+        }
+    };
+
+    if let Some(d) = parse_duration(s) {
+        return Ok(format!(
+            "{}{}{}",
+            shift(-d)?,
+            BLOCK_SEP,
+            shift(chrono::Duration::zero())?
+        ));
+    }
+
+    if let Some(d) = s.strip_prefix(BLOCK_SEP).and_then(parse_duration) {
+        let from = shift(-d)?;
+        return Ok(format!("{}{}{}", from, BLOCK_SEP, from));
     }
+
+    let mut half_blocks: Vec<String> = vec![];
+    for part in s.split(BLOCK_SEP) {
+        let previous = half_blocks.last().map(|h| h.as_str());
+        match (previous, parse_duration(part)) {
+            (Some("now"), Some(d)) => *half_blocks.last_mut().unwrap() = shift(-d)?,
+            _ => match part.split_once('+').map(|(t, d)| (t, parse_duration(d))) {
+                Some(("now", Some(d))) => half_blocks.push(shift(d)?),
+                _ => half_blocks.push(part.to_string()),
+            },
+        }
+    }
+
+    Ok(half_blocks
+        .into_iter()
+        .map(|h| match h.as_str() {
+            "now" => fmt::format_block_date(now),
+            _ => h,
+        })
+        .collect::<Vec<_>>()
+        .join(BLOCK_SEP))
 }
 
 fn normalize_half_block(s: &str) -> String {
@@ -66,10 +199,6 @@ fn normalize_half_block(s: &str) -> String {
         return s.to_string();
     }
 
-    if s == "now" {
-        return Local::now().format(BLOCK_FORMAT).to_string();
-    }
-
     match s.len() {
         5 => s.to_string(),
         4 => format!("{}:{}", &s[0..2], &s[2..4]),
@@ -87,15 +216,56 @@ fn normalize_block(s: &str) -> String {
         .join(BLOCK_SEP)
 }
 
-pub fn infer_block(block: &str, day: &Day) -> Block {
-    let mut normalized_block = normalize_block(block);
+pub fn infer_block(block: &str, day: &Day, now: &DateTime<Local>) -> Block {
+    try_infer_block(block, day, now).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_infer_block(block: &str, day: &Day, now: &DateTime<Local>) -> Result<Block, String> {
+    let inferred = infer_times(block, day, now)?;
+
+    if inferred.to < inferred.from {
+        return Err(format!("Block '{}' ends before it starts", block));
+    }
+
+    Ok(inferred)
+}
+
+fn infer_times(block: &str, day: &Day, now: &DateTime<Local>) -> Result<Block, String> {
+    let (times, mut project) = parse::parse_project(block);
+    let invalid = || format!("Invalid block '{}'", block);
+
+    // Extends the ongoing or last block by a duration, e.g. `+1h30`
+    if let Some(d) = times.strip_prefix('+').and_then(parse_duration) {
+        let last = day
+            .find_ongoing_block()
+            .or_else(|| day.blocks.iter().max_by_key(|b| b.to))
+            .ok_or("No block to extend")?;
+
+        let to = last.to + d;
+        if to.date().naive_local() != day.date {
+            return Err(format!("Block '{}' leaves the day", block));
+        }
+
+        let mut block = Block::new(last.from, to);
+        block.project = project.or_else(|| last.project.clone());
+        return Ok(block);
+    }
+
+    let mut normalized_block = normalize_block(&resolve_relative(times, now)?);
 
     // Full block, no need to infer completion
     if normalized_block.contains(BLOCK_SEP) {
-        return parse::parse_block(&day.date, normalized_block.as_str());
+        let mut block = parse::try_parse_block_times(&day.date, normalized_block.as_str())
+            .map_err(|_| invalid())?;
+        block.project = project;
+        return Ok(block);
     }
 
     let ongoing_block = day.find_ongoing_block();
+    if let Some(b) = ongoing_block {
+        project = project.or_else(|| b.project.clone());
+    }
+
     normalized_block = match ongoing_block {
         Some(b) => format!(
             "{}{}{}",
@@ -106,7 +276,10 @@ pub fn infer_block(block: &str, day: &Day) -> Block {
         None => format!("{}{}{}", normalized_block, BLOCK_SEP, normalized_block),
     };
 
-    parse::parse_block(&day.date, normalized_block.as_str())
+    let mut block = parse::try_parse_block_times(&day.date, normalized_block.as_str())
+        .map_err(|_| invalid())?;
+    block.project = project;
+    Ok(block)
 }
 
 #[cfg(test)]
@@ -129,6 +302,137 @@ mod tests {
         assert_eq!("8:00-09:33", normalize_block("8-933"));
     }
 
+    #[test]
+    fn test_relative_blocks() {
+        let now = Local.ymd(2022, 2, 12).and_hms(12, 0, 0);
+        let resolve = |s| resolve_relative(s, &now).unwrap();
+        assert_eq!("11:30", resolve("now-30m"));
+        assert_eq!("12:15", resolve("now+15m"));
+        assert_eq!("11:45-11:45", resolve("-15m"));
+        assert_eq!("10:00-12:00", resolve("2h"));
+        assert_eq!("08:30-10:30", resolve("08:30-now-1h30"));
+        assert_eq!("830-1230", resolve("830-1230"));
+
+        let now = Local.ymd(2022, 2, 12).and_hms(0, 10, 0);
+        assert_eq!(
+            Err("Block 'now-30m' leaves the day".to_string()),
+            resolve_relative("now-30m", &now)
+        );
+        assert!(resolve_relative("1h", &now).is_err());
+
+        let now = Local.ymd(2022, 2, 12).and_hms(12, 0, 0);
+        let mut day = Day::from_date(&now.date().naive_local());
+        day.add_block(&infer_block("8", &day, &now));
+        assert_eq!(
+            "11:45-11:45",
+            fmt::format_block(
+                &infer_block("-15m", &day, &now),
+                fmt::OutputMode::File,
+                fmt::Precision::Minutes
+            )
+        );
+
+        assert_eq!(Some(chrono::Duration::minutes(90)), parse_duration("1h30m"));
+        assert_eq!(Some(chrono::Duration::minutes(45)), parse_duration("45m"));
+        assert_eq!(None, parse_duration("1230"));
+        assert_eq!(None, parse_duration("m"));
+    }
+
+    #[test]
+    fn test_extend_block() {
+        let now = Local.ymd(2022, 2, 12).and_hms(12, 0, 0);
+        let mut day = Day::from_date(&now.date().naive_local());
+        day.add_block(&infer_block("8@acme", &day, &now));
+
+        let block = infer_block("+1h30", &day, &now);
+        assert_eq!(
+            "08:00-09:30@acme",
+            fmt::format_block(&block, fmt::OutputMode::File, fmt::Precision::Minutes)
+        );
+
+        day.add_block(&block);
+        let block = infer_block("+15m", &day, &now);
+        assert_eq!(
+            "08:00-09:45@acme",
+            fmt::format_block(&block, fmt::OutputMode::File, fmt::Precision::Minutes)
+        );
+
+        let mut day = Day::from_date(&now.date().naive_local());
+        day.add_block(&infer_block("23-2330", &day, &now));
+        assert_eq!(
+            Err("Block '+1h' leaves the day".to_string()),
+            try_infer_block("+1h", &day, &now)
+        );
+    }
+
+    #[test]
+    fn test_invalid_blocks() {
+        let now = Local.ymd(2022, 2, 12).and_hms(12, 0, 0);
+        let day = Day::from_date(&now.date().naive_local());
+
+        assert_eq!(
+            Err("Invalid block 'xyz'".to_string()),
+            try_infer_block("xyz", &day, &now)
+        );
+        assert!(try_infer_block("+1h", &day, &now).is_err());
+        assert_eq!(
+            Err("Block '14-12' ends before it starts".to_string()),
+            try_infer_block("14-12", &day, &now)
+        );
+    }
+
+    #[test]
+    fn test_infer_dates() {
+        let date = NaiveDate::from_ymd(2022, 2, 16);
+        let dates = |input| {
+            infer_dates(input, &date)
+                .iter()
+                .map(|d| d.format("%d.%m").to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec!["14.02"], dates("14"));
+        assert_eq!(vec!["14.02", "15.02", "16.02"], dates("14..16"));
+        assert_eq!(vec!["27.02", "28.02", "01.03"], dates("27..1.3"));
+        assert_eq!(vec!["28.02", "01.03"], dates("28..1"));
+        assert_eq!(vec!["20.02", "21.02"], dates("sun..mon"));
+        assert_eq!(vec!["01.02", "14.02", "15.02"], dates("14,1,14..15"));
+        assert_eq!(vec!["14.02", "15.02", "18.02"], dates("mon,tue,fri"));
+        assert_eq!(vec!["16.02", "17.02", "18.02"], dates("wednesday..fri"));
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        let date = NaiveDate::from_ymd(2022, 2, 16);
+        assert_eq!(
+            Err("Invalid date '31'".to_string()),
+            try_infer_dates("28..31", &date)
+        );
+        assert_eq!(
+            Err("Invalid date '30'".to_string()),
+            try_infer_dates("31.1..30", &date)
+        );
+        assert_eq!(
+            Err("Invalid date '14.x'".to_string()),
+            try_infer_date("14.x", &date)
+        );
+        assert_eq!(
+            Err("Date range 16..1.2 ends before it starts".to_string()),
+            try_infer_dates("16..1.2", &date)
+        );
+    }
+
+    #[test]
+    fn test_infer_month() {
+        assert_eq!(Ok((3, 2022)), try_infer_month("3", (2, 2022)));
+        assert_eq!(Ok((12, 2021)), try_infer_month("12.21", (2, 2022)));
+        assert_eq!(
+            Err("Invalid month '13'".to_string()),
+            try_infer_month("13", (2, 2022))
+        );
+        assert!(try_infer_month("3.x", (2, 2022)).is_err());
+    }
+
     #[test]
     fn test_prev_month() {
         assert_eq!((12, 2022), prev_month((1, 2023)));
@@ -140,4 +444,18 @@ mod tests {
         assert_eq!((1, 2024), next_month((12, 2023)));
         assert_eq!((2, 2022), next_month((1, 2022)));
     }
+
+    #[test]
+    fn test_infer_block_with_project() {
+        let mut day = Day::from_date(&NaiveDate::from_ymd(2022, 2, 12));
+        let block = infer_block("830-12@acme", &day, &now_minute());
+        assert_eq!(Some("acme".to_string()), block.project);
+
+        day.add_block(&infer_block("13@acme", &day, &now_minute()));
+        let block = infer_block("1530", &day, &now_minute());
+        assert_eq!(
+            "13:00-15:30@acme",
+            fmt::format_block(&block, fmt::OutputMode::File, fmt::Precision::Minutes)
+        );
+    }
 }
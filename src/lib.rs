@@ -0,0 +1,40 @@
+//! Time tracking in plain text BRF files, one file per month like
+//! `hours/2022-2.txt`.
+//!
+//! The `punch` binary is a thin client of this crate. Other tools can use the
+//! domain types (`month`, `day`, `block`), the parser (`parse`), the
+//! formatters (`fmt`, `timesheet`, `stats`) and the store (`card`, `store`).
+//! Functions that panic on bad input, like `Block::parse`, `Month::from_brf`
+//! or `infer::infer_date`, have `try_` variants returning a `Result`. The
+//! command line itself (argument parsing, the shell and the TUI) lives in the
+//! binary.
+//!
+//! ```
+//! use punch::fmt::format_duration;
+//! use punch::parse::try_parse_month;
+//!
+//! let contents = "February 2022\n\n01.02.22   08:00-12:00   Total: 04:00\n";
+//! let month = try_parse_month(contents, 2022, 2).unwrap();
+//!
+//! assert_eq!("04:00", format_duration(&month.duration()));
+//! assert!(try_parse_month("February 2022\n\n01.02.22   8-12\n", 2022, 2).is_err());
+//! assert!(try_parse_month("February 2022\n\n01.02.22   17:00-08:00\n", 2022, 2).is_err());
+//! ```
+
+pub mod block;
+pub mod card;
+pub mod chart;
+pub mod compliance;
+pub mod config;
+pub mod day;
+pub mod fmt;
+pub mod infer;
+pub mod locale;
+pub mod month;
+pub mod parse;
+pub mod rounding;
+pub mod search;
+pub mod stats;
+pub mod store;
+pub mod theme;
+pub mod timesheet;
@@ -0,0 +1,320 @@
+use chrono::format::Item;
+use chrono::format::StrftimeItems;
+use chrono::prelude::*;
+use std::fmt::Write;
+
+use crate::config::Config;
+use crate::month::Month;
+
+/// Terminal date format unless `date_format` is configured.
+pub const DEFAULT_DATE_FORMAT: &str = "%a   %d.%m.%y";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+    French,
+    Italian,
+}
+
+/// Month and weekday names and the date format of the terminal output and
+/// reports, configured like `locale = de` and `date_format = %a %d.%m.%Y`.
+/// BRF files are always written in English with `parse::DATE_FORMAT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    pub language: Language,
+    pub date_format: String,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            language: Language::English,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+        }
+    }
+}
+
+impl Locale {
+    pub fn from_config(config: &Config) -> Self {
+        Self::try_from_config(config).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_config(config: &Config) -> Result<Self, String> {
+        let language = match config.get("locale") {
+            None | Some("en") => Language::English,
+            Some("de") => Language::German,
+            Some("fr") => Language::French,
+            Some("it") => Language::Italian,
+            Some(l) => return Err(format!("Unknown locale {}", l)),
+        };
+
+        let date_format = config.get("date_format").unwrap_or(DEFAULT_DATE_FORMAT);
+        if !is_date_format(date_format) {
+            return Err(format!("Invalid date_format {}", date_format));
+        }
+
+        Ok(Self {
+            language,
+            date_format: date_format.to_string(),
+        })
+    }
+
+    /// Formats the date with the configured date format.
+    pub fn format_date(&self, date: &NaiveDate) -> String {
+        self.format(date, &self.date_format)
+    }
+
+    /// Like `NaiveDate::format`, but with localized names for `%a`, `%A`,
+    /// `%b` and `%B`.
+    pub fn format(&self, date: &NaiveDate, format: &str) -> String {
+        let mut localized = String::new();
+        let mut chars = format.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                localized.push(c);
+                continue;
+            }
+
+            let name = match chars.next() {
+                Some('a') => self.weekday(date.weekday()).to_string(),
+                Some('A') => self.weekday_name(date.weekday()).to_string(),
+                Some('b') => self.short_month_name(date.month()).to_string(),
+                Some('B') => self.month_name(date.month()).to_string(),
+                Some(other) => {
+                    localized.push(c);
+                    localized.push(other);
+                    continue;
+                }
+                None => {
+                    localized.push(c);
+                    break;
+                }
+            };
+            localized.push_str(&name.replace('%', "%%"));
+        }
+
+        date.format(&localized).to_string()
+    }
+
+    /// Like `Month::title`, e.g. `Februar 2022`.
+    pub fn month_title(&self, month: &Month) -> String {
+        let name = self.month_name(month.month as u32);
+        let mut chars = name.chars();
+        let capitalized = match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+            None => "".to_string(),
+        };
+
+        format!("{} {}", capitalized, month.year)
+    }
+
+    /// Abbreviated weekday name, the same width for all days of a language.
+    pub fn weekday(&self, weekday: Weekday) -> &'static str {
+        let names = match self.language {
+            Language::English => ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+            Language::German => ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+            Language::French => ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"],
+            Language::Italian => ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+        };
+
+        names[weekday.num_days_from_monday() as usize]
+    }
+
+    pub fn weekday_name(&self, weekday: Weekday) -> &'static str {
+        let names = match self.language {
+            Language::English => [
+                "Monday",
+                "Tuesday",
+                "Wednesday",
+                "Thursday",
+                "Friday",
+                "Saturday",
+                "Sunday",
+            ],
+            Language::German => [
+                "Montag",
+                "Dienstag",
+                "Mittwoch",
+                "Donnerstag",
+                "Freitag",
+                "Samstag",
+                "Sonntag",
+            ],
+            Language::French => [
+                "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+            ],
+            Language::Italian => [
+                "lunedì",
+                "martedì",
+                "mercoledì",
+                "giovedì",
+                "venerdì",
+                "sabato",
+                "domenica",
+            ],
+        };
+
+        names[weekday.num_days_from_monday() as usize]
+    }
+
+    pub fn month_name(&self, month: u32) -> &'static str {
+        let names = match self.language {
+            Language::English => [
+                "January",
+                "February",
+                "March",
+                "April",
+                "May",
+                "June",
+                "July",
+                "August",
+                "September",
+                "October",
+                "November",
+                "December",
+            ],
+            Language::German => [
+                "Januar",
+                "Februar",
+                "März",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            Language::French => [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+            Language::Italian => [
+                "gennaio",
+                "febbraio",
+                "marzo",
+                "aprile",
+                "maggio",
+                "giugno",
+                "luglio",
+                "agosto",
+                "settembre",
+                "ottobre",
+                "novembre",
+                "dicembre",
+            ],
+        };
+
+        names[month as usize - 1]
+    }
+
+    pub fn short_month_name(&self, month: u32) -> &'static str {
+        let names = match self.language {
+            Language::English => [
+                "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+            ],
+            Language::German => [
+                "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+            ],
+            Language::French => [
+                "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.",
+                "nov.", "déc.",
+            ],
+            Language::Italian => [
+                "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+            ],
+        };
+
+        names[month as usize - 1]
+    }
+}
+
+/// Whether the format is valid and only uses date specifiers, which
+/// `NaiveDate::format` could otherwise only report by panicking.
+fn is_date_format(format: &str) -> bool {
+    let date = NaiveDate::from_ymd(2000, 1, 1);
+    let mut formatted = String::new();
+    !StrftimeItems::new(format).any(|item| item == Item::Error)
+        && write!(formatted, "{}", date.format(format)).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(config: &str) -> Locale {
+        Locale::from_config(&Config::parse(config))
+    }
+
+    #[test]
+    fn test_default_locale() {
+        let date = NaiveDate::from_ymd(2022, 2, 3);
+        let locale = locale("");
+
+        assert_eq!(
+            date.format(DEFAULT_DATE_FORMAT).to_string(),
+            locale.format_date(&date)
+        );
+        assert_eq!(
+            "Thursday, 3 February 2022 (Feb)",
+            locale.format(&date, "%A, %-d %B %Y (%b)")
+        );
+    }
+
+    #[test]
+    fn test_localized_dates() {
+        let date = NaiveDate::from_ymd(2022, 3, 3);
+
+        assert_eq!("Do   03.03.22", locale("locale = de").format_date(&date));
+        assert_eq!(
+            "jeudi 3 mars 2022",
+            locale("locale = fr\ndate_format = %A %-d %B %Y").format_date(&date)
+        );
+        assert_eq!(
+            "gio 03/03/2022 100%",
+            locale("locale = it\ndate_format = %a %d/%m/%Y 100%%").format_date(&date)
+        );
+    }
+
+    #[test]
+    fn test_month_title() {
+        let month = Month::new(3, 2022, vec![]);
+
+        assert_eq!("March 2022", locale("").month_title(&month));
+        assert_eq!("März 2022", locale("locale = de").month_title(&month));
+        assert_eq!("Mars 2022", locale("locale = fr").month_title(&month));
+        assert_eq!("Marzo 2022", locale("locale = it").month_title(&month));
+    }
+
+    #[test]
+    fn test_unknown_locale() {
+        assert_eq!(
+            Err("Unknown locale es".to_string()),
+            Locale::try_from_config(&Config::parse("locale = es"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_date_format() {
+        assert_eq!(
+            Err("Invalid date_format %a %Q".to_string()),
+            Locale::try_from_config(&Config::parse("date_format = %a %Q"))
+        );
+        assert!(Locale::try_from_config(&Config::parse("date_format = %d %H:%M")).is_err());
+        assert!(Locale::try_from_config(&Config::parse("date_format = %d.%m. 100%%")).is_ok());
+    }
+}
@@ -1,17 +1,17 @@
 use clap::Parser;
 
-mod block;
 mod cli;
 mod clock;
-mod day;
-mod fmt;
-mod infer;
-mod month;
-mod parse;
+mod completions;
+mod import;
+mod opener;
+mod shell;
+mod tui;
 
 use cli::Args;
 
 fn main() {
     let args = Args::parse();
+    args.check_conflicts().unwrap_or_else(|e| e.exit());
     clock::punch(&args);
 }
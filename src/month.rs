@@ -1,13 +1,15 @@
 use chrono::prelude::*;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::time::Duration;
 
 use crate::day::Day;
 use crate::parse;
+use crate::parse::ParseError;
 
 type DayMap = HashMap<NaiveDate, Day>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Month {
     pub month: u8,
     pub year: u16,
@@ -29,6 +31,10 @@ impl Month {
         parse::parse_month(contents, year, month)
     }
 
+    pub fn try_from_brf(contents: &str, year: u16, month: u8) -> Result<Self, ParseError> {
+        parse::try_parse_month(contents, year, month)
+    }
+
     pub fn add_day(&mut self, date: &NaiveDate) {
         match self.days.get(date) {
             Some(_day) => (),
@@ -47,6 +53,23 @@ impl Month {
         self.days.values().map(|d| d.duration()).sum::<Duration>()
     }
 
+    pub fn live_duration(&self, now: &DateTime<Local>) -> Duration {
+        self.days
+            .values()
+            .map(|d| d.live_duration(now))
+            .sum::<Duration>()
+    }
+
+    /// Expected working time, i.e. the daily target for every weekday in the
+    /// month that isn't a holiday.
+    pub fn target_duration(
+        &self,
+        daily_target: &Duration,
+        holidays: &HashSet<NaiveDate>,
+    ) -> Duration {
+        *daily_target * self.num_workdays(holidays)
+    }
+
     pub fn max_num_blocks_in_day(&self) -> usize {
         if self.days.is_empty() {
             return 0;
@@ -73,14 +96,34 @@ impl Month {
         for day in 1..=self.total_num_days() {
             let date = NaiveDate::from_ymd(self.year as i32, self.month as u32, day as u32);
 
-            if let None = days.get(&date) {
-                days.insert(date, Day::from_date(&date));
-            }
+            days.entry(date).or_insert_with(|| Day::from_date(&date));
         }
 
         sorted_by_key(&days)
     }
 
+    /// Weekdays up to and including `until` without any entry, not even a
+    /// comment. Holidays aren't missing.
+    pub fn missing_workdays(
+        &self,
+        until: &NaiveDate,
+        holidays: &HashSet<NaiveDate>,
+    ) -> Vec<NaiveDate> {
+        self.full_sorted_days()
+            .into_iter()
+            .filter(|d| d.date <= *until && is_workday(&d.date) && d.is_empty())
+            .filter(|d| !holidays.contains(&d.date))
+            .map(|d| d.date)
+            .collect()
+    }
+
+    fn num_workdays(&self, holidays: &HashSet<NaiveDate>) -> u32 {
+        self.full_sorted_days()
+            .iter()
+            .filter(|d| is_workday(&d.date) && !holidays.contains(&d.date))
+            .count() as u32
+    }
+
     fn total_num_days(&self) -> i64 {
         NaiveDate::from_ymd(
             match self.month {
@@ -116,6 +159,10 @@ impl Month {
     }
 }
 
+pub fn is_workday(date: &NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
 fn sorted_by_key(days: &DayMap) -> Vec<Day> {
     let mut dates = days.keys().collect::<Vec<_>>();
     dates.sort();
@@ -125,3 +172,52 @@ fn sorted_by_key(days: &DayMap) -> Vec<Day> {
         .map(|k| days.get(k).unwrap().clone())
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_workdays() {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-12:00   Total: 04:00
+            02.02.22   Total: 00:00   sick
+
+            Total: 04:00
+        "#;
+        let month = Month::from_brf(contents, 2022, 2);
+
+        assert_eq!(
+            vec![
+                NaiveDate::from_ymd(2022, 2, 3),
+                NaiveDate::from_ymd(2022, 2, 4),
+                NaiveDate::from_ymd(2022, 2, 7),
+            ],
+            month.missing_workdays(&NaiveDate::from_ymd(2022, 2, 7), &HashSet::new())
+        );
+
+        let holidays = HashSet::from([NaiveDate::from_ymd(2022, 2, 4)]);
+        assert_eq!(
+            vec![
+                NaiveDate::from_ymd(2022, 2, 3),
+                NaiveDate::from_ymd(2022, 2, 7)
+            ],
+            month.missing_workdays(&NaiveDate::from_ymd(2022, 2, 7), &holidays)
+        );
+    }
+
+    #[test]
+    fn test_target_duration() {
+        let month = Month::new(2, 2022, vec![]);
+        let eight = Duration::from_secs(8 * 3600);
+        let holidays = HashSet::from([
+            NaiveDate::from_ymd(2022, 2, 4),
+            NaiveDate::from_ymd(2022, 2, 5),
+        ]);
+
+        assert_eq!(eight * 20, month.target_duration(&eight, &HashSet::new()));
+        assert_eq!(eight * 19, month.target_duration(&eight, &holidays));
+    }
+}
@@ -0,0 +1,77 @@
+use punch::config::Config;
+use std::process::Command;
+
+/// Opens files and directories with the configured command (`opener = ...`)
+/// or the platform's default, falling back to printing the path.
+pub struct Opener {
+    command: Option<String>,
+}
+
+impl Opener {
+    pub fn new(config: &Config) -> Self {
+        let command = config
+            .get("opener")
+            .map(|c| c.to_string())
+            .or_else(|| default_command().map(|c| c.to_string()));
+
+        Self { command }
+    }
+
+    pub fn open(&self, path: &str) {
+        if !self.try_open(path) {
+            println!("{}", path);
+        }
+    }
+
+    fn try_open(&self, path: &str) -> bool {
+        let command = match &self.command {
+            Some(c) => c,
+            None => return false,
+        };
+
+        let mut parts = command.split_whitespace();
+        let program = match parts.next() {
+            Some(p) => p,
+            None => return false,
+        };
+
+        Command::new(program)
+            .args(parts)
+            .arg(path)
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    }
+}
+
+fn default_command() -> Option<&'static str> {
+    if cfg!(target_os = "macos") {
+        Some("open")
+    } else if cfg!(target_os = "windows") {
+        Some("explorer")
+    } else if cfg!(unix) {
+        Some("xdg-open")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_command() {
+        let opener = Opener::new(&Config::parse("opener = code -n"));
+        assert_eq!(Some("code -n".to_string()), opener.command);
+    }
+
+    #[test]
+    fn test_failing_command() {
+        let opener = Opener::new(&Config::parse("opener = false"));
+        assert!(!opener.try_open("."));
+
+        let opener = Opener::new(&Config::parse("opener = punch-no-such-opener"));
+        assert!(!opener.try_open("."));
+    }
+}
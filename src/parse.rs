@@ -1,96 +1,374 @@
 use chrono::prelude::*;
+use std::fmt;
 
 use crate::block::Block;
 use crate::day::Day;
+use crate::fmt::format_block_date;
 use crate::month::Month;
 
 pub const DATE_FORMAT: &str = "%d.%m.%y";
 pub const TOTAL_PAT: &str = "Total:";
+pub const PROJECT_SEP: char = '@';
+pub const NOTE_QUOTE: char = '"';
+pub const ESCAPE: char = '\\';
+
+/// A line of a BRF file that couldn't be parsed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
 
 pub fn parse_month(contents: &str, year: u16, month: u8) -> Month {
-    let mut lines = contents.trim().lines();
-    lines.next();
+    try_parse_month(contents, year, month).unwrap_or_else(|e| panic!("Could not parse BRF: {}", e))
+}
+
+pub fn try_parse_month(contents: &str, year: u16, month: u8) -> Result<Month, ParseError> {
+    // The first line is the title
+    let lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .skip(1);
 
+    let empty_month = Month::new(month, year, vec![]);
     let mut days: Vec<Day> = Vec::new();
-    for line in lines {
+    for (index, line) in lines {
         let trimmed_line = line.trim();
-        if trimmed_line.is_empty() || trimmed_line.starts_with(TOTAL_PAT) {
+        if trimmed_line.starts_with(TOTAL_PAT) {
             continue;
         }
 
-        let day = Day::from_brf(trimmed_line);
+        let error = |message| ParseError {
+            line: index + 1,
+            message,
+        };
+        let day = Day::try_from_brf(trimmed_line).map_err(error)?;
+        if let Some(problem) = validate_day(&day, &empty_month).into_iter().next() {
+            return Err(error(problem));
+        }
         days.push(day);
     }
 
-    Month::new(month, year, days)
+    Ok(Month::new(month, year, days))
 }
 
-pub fn parse_day(line: &str) -> Day {
-    let mut day_iter = line.split_whitespace();
-    let date = parse_date(day_iter.next().unwrap());
-    let mut blocks: Vec<Block> = Vec::new();
-    let mut comment = None;
+/// Reports days that don't belong to the month and blocks that end before
+/// they start or overlap with other blocks.
+pub fn validate_month(month: &Month) -> Vec<String> {
+    month
+        .sorted_days()
+        .iter()
+        .flat_map(|day| validate_day(day, month))
+        .collect()
+}
+
+fn validate_day(day: &Day, month: &Month) -> Vec<String> {
+    let mut problems = vec![];
+    let date = day.date.format(DATE_FORMAT);
+
+    if day.date.year() != month.year as i32 || day.date.month() != month.month as u32 {
+        problems.push(format!("{} is not in {}", date, month.title()));
+    }
+
+    for block in day.blocks.iter() {
+        if block.to < block.from {
+            problems.push(format!(
+                "{} block {} ends before it starts",
+                date,
+                format_block_date(&block.from)
+            ));
+        }
+    }
+
+    for pair in day.blocks.windows(2) {
+        if pair[0].to > pair[1].from {
+            problems.push(format!(
+                "{} blocks starting at {} and {} overlap",
+                date,
+                format_block_date(&pair[0].from),
+                format_block_date(&pair[1].from)
+            ));
+        }
+    }
+
+    problems
+}
+
+/// A whitespace separated word or a quoted block note of a BRF line.
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Note(String),
+}
+
+/// Splits the part of a BRF line before the total into words and quoted
+/// notes. Also returns the offset of the total, if there is one.
+fn tokenize(line: &str) -> Result<(Vec<Token<'_>>, Option<usize>), String> {
+    let mut tokens = vec![];
+    let mut chars = line.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c == NOTE_QUOTE {
+            chars.next();
+            let mut note = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, ESCAPE)) => match chars.next() {
+                        Some((_, e)) => {
+                            note.push(ESCAPE);
+                            note.push(e);
+                        }
+                        None => return Err(format!("unterminated note {}", &line[start..])),
+                    },
+                    Some((_, NOTE_QUOTE)) => break,
+                    Some((_, c)) => note.push(c),
+                    None => return Err(format!("unterminated note {}", &line[start..])),
+                }
+            }
+            tokens.push(Token::Note(unescape(&note)));
+            continue;
+        }
 
-    for block_str in day_iter {
-        if block_str.starts_with(TOTAL_PAT) {
-            comment = parse_comment(line);
-            break;
+        let end = line[start..]
+            .find(char::is_whitespace)
+            .map_or(line.len(), |i| start + i);
+        let word = &line[start..end];
+        if word.starts_with(TOTAL_PAT) {
+            return Ok((tokens, Some(start)));
         }
 
-        blocks.push(parse_block(&date, block_str));
+        tokens.push(Token::Word(word));
+        while chars.next_if(|&(i, _)| i < end).is_some() {}
     }
 
-    Day {
+    Ok((tokens, None))
+}
+
+/// Escapes backslashes and line breaks, so multiline text fits on a single
+/// BRF line.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            ESCAPE => escaped.push_str("\\\\"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverts `escape` and escaped quotes, keeping unknown escape sequences
+/// like in `C:\temp` as they are.
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != ESCAPE {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some(e @ (ESCAPE | NOTE_QUOTE)) => unescaped.push(e),
+            Some(e) => {
+                unescaped.push(ESCAPE);
+                unescaped.push(e);
+            }
+            None => unescaped.push(ESCAPE),
+        }
+    }
+    unescaped
+}
+
+/// Quotes a block note for the BRF file, escaping quotes, backslashes and
+/// line breaks.
+pub fn quote_note(note: &str) -> String {
+    let escaped = escape(note).replace(NOTE_QUOTE, "\\\"");
+    format!("{}{}{}", NOTE_QUOTE, escaped, NOTE_QUOTE)
+}
+
+pub fn parse_day(line: &str) -> Day {
+    try_parse_day(line).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse_day(line: &str) -> Result<Day, String> {
+    let (tokens, total) = tokenize(line)?;
+    let mut tokens = tokens.into_iter();
+    let date = match tokens.next() {
+        Some(Token::Word(w)) => parse_date(w)?,
+        _ => return Err(format!("invalid date in '{}'", line.trim())),
+    };
+
+    let mut blocks: Vec<Block> = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Word(block_str) => blocks.push(try_parse_block(&date, block_str)?),
+            Token::Note(note) => match blocks.last_mut() {
+                Some(b) => b.note = Some(note),
+                None => return Err(format!("note {} without block", quote_note(&note))),
+            },
+        }
+    }
+    let comment = total.and_then(|offset| parse_comment(&line[offset..]));
+
+    Ok(Day {
         date,
         blocks,
         comment,
-    }
+    })
 }
 
-pub fn parse_date(date: &str) -> NaiveDate {
-    NaiveDate::parse_from_str(date, DATE_FORMAT).unwrap()
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|_| format!("invalid date '{}'", date))
 }
 
+/// Everything after the total hours. Comments with escape sequences are
+/// quoted like notes, others are kept as they are, so that comments like
+/// `C:\new` stay intact.
 pub fn parse_comment(line: &str) -> Option<String> {
-    let parts = line.split(TOTAL_PAT).collect::<Vec<&str>>();
-    let str_after_total = parts.get(1).unwrap();
-    let mut iter = str_after_total.split_whitespace();
-    iter.next(); // discard total hours
+    let after_total = line.split_once(TOTAL_PAT)?.1.trim_start();
+    let comment_str = after_total
+        .split_once(char::is_whitespace)
+        .map_or("", |(_, c)| c)
+        .trim();
 
-    let comment_str = iter.collect::<Vec<&str>>().join(" ").trim().to_string();
     if comment_str.is_empty() {
         None
     } else {
-        Some(comment_str)
+        Some(unquote_comment(comment_str))
+    }
+}
+
+/// Quotes the comment for the BRF file if it has line breaks or would be
+/// mistaken for a quoted comment.
+pub fn quote_comment(comment: &str) -> String {
+    if comment.contains('\n') || unquote_comment(comment) != comment {
+        quote_note(comment)
+    } else {
+        comment.to_string()
+    }
+}
+
+fn unquote_comment(comment: &str) -> String {
+    let quoted = comment
+        .strip_prefix(NOTE_QUOTE)
+        .and_then(|c| c.strip_suffix(NOTE_QUOTE))
+        .filter(|c| c.contains(ESCAPE) && is_escaped(c));
+
+    match quoted {
+        Some(c) => unescape(c),
+        None => comment.to_string(),
     }
 }
 
+/// Whether all quotes and backslashes of the text are escaped.
+fn is_escaped(text: &str) -> bool {
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ESCAPE if chars.next().is_none() => return false,
+            NOTE_QUOTE => return false,
+            _ => (),
+        }
+    }
+    true
+}
+
 pub fn parse_hm(half_block: &str) -> (u32, u32) {
-    let parts = half_block.split(':').collect::<Vec<_>>();
+    try_parse_hm(half_block).unwrap()
+}
 
-    (
-        parts.get(0).unwrap().parse::<u32>().unwrap(),
-        parts.get(1).unwrap().parse::<u32>().unwrap(),
-    )
+pub fn try_parse_hm(half_block: &str) -> Result<(u32, u32), String> {
+    let error = || format!("invalid time '{}'", half_block);
+    let (hour, minute) = half_block.split_once(':').ok_or_else(error)?;
+    let hour = hour.parse::<u32>().map_err(|_| error())?;
+    let minute = minute.parse::<u32>().map_err(|_| error())?;
+
+    if hour < 24 && minute < 60 {
+        Ok((hour, minute))
+    } else {
+        Err(error())
+    }
+}
+
+/// Parses `HH:MM` or `HH:MM:SS`.
+pub fn try_parse_hms(half_block: &str) -> Result<(u32, u32, u32), String> {
+    match half_block.rsplit_once(':') {
+        Some((hm, second)) if hm.contains(':') => {
+            let (hour, minute) = try_parse_hm(hm)?;
+            match second.parse::<u32>() {
+                Ok(second) if second < 60 => Ok((hour, minute, second)),
+                _ => Err(format!("invalid time '{}'", half_block)),
+            }
+        }
+        _ => try_parse_hm(half_block).map(|(hour, minute)| (hour, minute, 0)),
+    }
 }
 
 pub fn parse_block(date: &NaiveDate, block_str: &str) -> Block {
-    let parts = block_str.split('-').collect::<Vec<_>>();
-    let from_str = parts.get(0).unwrap();
-    let (from_hour, from_min) = parse_hm(from_str);
+    try_parse_block(date, block_str).unwrap()
+}
+
+pub fn try_parse_block(date: &NaiveDate, block_str: &str) -> Result<Block, String> {
+    let block = try_parse_block_times(date, block_str)?;
+    if block.to < block.from {
+        return Err(format!("block '{}' ends before it starts", block_str));
+    }
 
-    let to_str = parts.get(1).unwrap();
-    let (to_hour, to_min) = parse_hm(to_str);
+    Ok(block)
+}
+
+/// Like `try_parse_block`, but allows blocks that end before they start, so
+/// callers can report them in their own words.
+pub fn try_parse_block_times(date: &NaiveDate, block_str: &str) -> Result<Block, String> {
+    let (times, project) = parse_project(block_str);
+    let (from_str, to_str) = times
+        .split_once('-')
+        .ok_or_else(|| format!("invalid block '{}'", block_str))?;
+    let (from_hour, from_min, from_sec) = try_parse_hms(from_str)?;
+    let (to_hour, to_min, to_sec) = try_parse_hms(to_str)?;
 
-    let from = Local
-        .ymd(date.year(), date.month(), date.day())
-        .and_hms(from_hour, from_min, 0);
+    // Times skipped by a DST change don't exist
+    let local = |hour, min, sec, time_str| {
+        Local
+            .from_local_datetime(&date.and_hms(hour, min, sec))
+            .earliest()
+            .ok_or_else(|| format!("invalid time '{}'", time_str))
+    };
+    let from = local(from_hour, from_min, from_sec, from_str)?;
+    let to = local(to_hour, to_min, to_sec, to_str)?;
 
-    let to = Local
-        .ymd(date.year(), date.month(), date.day())
-        .and_hms(to_hour, to_min, 0);
+    Ok(Block {
+        from,
+        to,
+        project,
+        note: None,
+    })
+}
 
-    Block { from, to }
+/// Splits a block like `08:00-12:00@acme` into the times and the project.
+pub fn parse_project(block_str: &str) -> (&str, Option<String>) {
+    match block_str.split_once(PROJECT_SEP) {
+        Some((times, project)) if !project.is_empty() => (times, Some(project.to_string())),
+        Some((times, _)) => (times, None),
+        None => (block_str, None),
+    }
 }
 
 #[cfg(test)]
@@ -100,13 +378,73 @@ mod tests {
     #[test]
     fn test_parse_block() {
         let today = NaiveDate::from_ymd(2022, 2, 12);
-        let expected = Block {
-            from: Local.ymd(2022, 2, 12).and_hms(8, 15, 0),
-            to: Local.ymd(2022, 2, 12).and_hms(12, 0, 0),
-        };
+        let mut expected = Block::new(
+            Local.ymd(2022, 2, 12).and_hms(8, 15, 0),
+            Local.ymd(2022, 2, 12).and_hms(12, 0, 0),
+        );
 
         assert_eq!(expected, parse_block(&today, "08:15-12:00"));
         assert_eq!(expected, parse_block(&today, "8:15-12:00"));
+
+        expected.project = Some("acme".to_string());
+        assert_eq!(expected, parse_block(&today, "08:15-12:00@acme"));
+
+        let expected = Block::new(
+            Local.ymd(2022, 2, 12).and_hms(8, 15, 30),
+            Local.ymd(2022, 2, 12).and_hms(8, 16, 0),
+        );
+        assert_eq!(expected, parse_block(&today, "08:15:30-08:16:00"));
+        assert_eq!(expected, parse_block(&today, "08:15:30-08:16"));
+        assert!(try_parse_block(&today, "08:15:60-08:16").is_err());
+    }
+
+    #[test]
+    fn test_parse_notes() {
+        let line = r#"01.02.22  09:00-10:30@acme "standup + review"  11:00-12:00 "say \"hi\" \\ bye"  Total: 02:30 "sick""#;
+        let day = parse_day(line);
+
+        assert_eq!(Some("standup + review".to_string()), day.blocks[0].note);
+        assert_eq!(Some("acme".to_string()), day.blocks[0].project);
+        assert_eq!(Some(r#"say "hi" \ bye"#.to_string()), day.blocks[1].note);
+        assert_eq!(Some(r#""sick""#.to_string()), day.comment);
+        assert_eq!(r#""say \"hi\" \\ bye""#, quote_note(r#"say "hi" \ bye"#));
+
+        assert!(try_parse_day(r#"01.02.22  "note"  09:00-10:30  Total: 01:30"#).is_err());
+        assert!(try_parse_day(r#"01.02.22  09:00-10:30 "note  Total: 01:30"#).is_err());
+    }
+
+    #[test]
+    fn test_escape() {
+        let text = "line 1\nC:\\temp \"quoted\"";
+        assert_eq!(r#"line 1\nC:\\temp "quoted""#, escape(text));
+        assert_eq!(text, unescape(&escape(text)));
+        assert_eq!(r#"C:\temp"#, unescape(r#"C:\temp"#));
+
+        let line = r#"01.02.22  09:00-10:30 "a\nb"  Total: 01:30  "one  two\nthree""#;
+        let day = parse_day(line);
+        assert_eq!(Some("a\nb".to_string()), day.blocks[0].note);
+        assert_eq!(Some("one  two\nthree".to_string()), day.comment);
+    }
+
+    #[test]
+    fn test_quote_comment() {
+        let line = r#"01.02.22  09:00-10:30  Total: 01:30  C:\new "dir""#;
+        assert_eq!(Some(r#"C:\new "dir""#.to_string()), parse_day(line).comment);
+
+        for comment in [
+            "sick",
+            r#"C:\new"#,
+            "one\ntwo",
+            r#""C:\new""#,
+            r#""say \"hi\"""#,
+        ] {
+            let quoted = quote_comment(comment);
+            let line = format!("01.02.22  Total: 00:00  {}", quoted);
+            assert_eq!(Some(comment.to_string()), parse_day(&line).comment);
+        }
+        assert_eq!("sick", quote_comment("sick"));
+        assert_eq!(r#"C:\new"#, quote_comment(r#"C:\new"#));
+        assert_eq!(r#""one\ntwo""#, quote_comment("one\ntwo"));
     }
 
     #[test]
@@ -131,7 +469,75 @@ mod tests {
             Total: 00:00
         "#;
 
-        let month = parse_month(&contents, 2022, 2);
+        let month = parse_month(contents, 2022, 2);
         assert_eq!(0, month.days.len());
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-12:00   Total: 04:00
+            02.02.22   08:00-25:00   Total: 04:00
+
+            Total: 08:00
+        "#;
+
+        assert_eq!(
+            Err(ParseError {
+                line: 5,
+                message: "invalid time '25:00'".to_string()
+            }),
+            try_parse_month(contents, 2022, 2)
+        );
+        assert_eq!(
+            Err("invalid date '31.02.22'".to_string()),
+            try_parse_day("31.02.22   Total: 00:00")
+        );
+        assert_eq!(
+            Err("invalid block '08:00'".to_string()),
+            try_parse_day("01.02.22   08:00   Total: 00:00")
+        );
+    }
+
+    #[test]
+    fn test_validate_month() {
+        let days = vec![
+            Day::from_brf("01.02.22   08:00-12:00   11:00-13:00"),
+            Day::from_brf("02.03.22   08:00-12:00"),
+            Day::from_brf("03.02.22   08:00-12:00"),
+        ];
+        let month = Month::new(2, 2022, days);
+
+        assert_eq!(
+            vec![
+                "01.02.22 blocks starting at 08:00 and 11:00 overlap",
+                "02.03.22 is not in February 2022",
+            ],
+            validate_month(&month)
+        );
+
+        let error = |contents, message: &str| {
+            assert_eq!(
+                Err(ParseError {
+                    line: 3,
+                    message: message.to_string()
+                }),
+                try_parse_month(contents, 2022, 2)
+            )
+        };
+        error(
+            "February 2022\n\n01.02.22   08:00-12:00   11:00-13:00\n",
+            "01.02.22 blocks starting at 08:00 and 11:00 overlap",
+        );
+        error(
+            "February 2022\n\n02.03.22   08:00-12:00\n",
+            "02.03.22 is not in February 2022",
+        );
+        error(
+            "February 2022\n\n01.02.22   17:00-08:00\n",
+            "block '17:00-08:00' ends before it starts",
+        );
+    }
 }
@@ -0,0 +1,265 @@
+use chrono::prelude::*;
+use std::time::Duration;
+
+use crate::block::Block;
+use crate::day::Day;
+use crate::month::Month;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Increments in minutes that rounding supports.
+pub const ROUNDING_INCREMENTS: [u64; 4] = [5, 6, 10, 15];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RoundingMode {
+    Nearest,
+    Up,
+    Down,
+}
+
+/// Whether each block is rounded on its own or only the day total.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RoundingScope {
+    Block,
+    Day,
+}
+
+/// When rounding is applied. `Report` only affects the totals shown,
+/// `Punch` also rounds the times of newly added blocks before they're
+/// written to the BRF file.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RoundingApply {
+    Report,
+    Punch,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Rounding {
+    pub minutes: u64,
+    pub mode: RoundingMode,
+    pub scope: RoundingScope,
+    pub apply: RoundingApply,
+}
+
+impl Rounding {
+    pub fn new(minutes: u64) -> Self {
+        Self::try_new(minutes).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(minutes: u64) -> Result<Self, String> {
+        if !ROUNDING_INCREMENTS.contains(&minutes) {
+            return Err(format!(
+                "Rounding increment must be one of 5, 6, 10 or 15 minutes, got {}",
+                minutes
+            ));
+        }
+
+        Ok(Self {
+            minutes,
+            mode: RoundingMode::Nearest,
+            scope: RoundingScope::Block,
+            apply: RoundingApply::Report,
+        })
+    }
+
+    pub fn round_duration(&self, duration: &Duration) -> Duration {
+        Duration::from_secs(self.round_secs(duration.as_secs()))
+    }
+
+    /// Rounds the wall clock time, staying within the same day. Times that
+    /// don't exist locally, e.g. when DST starts, are left as they are.
+    pub fn round_dt(&self, dt: &DateTime<Local>) -> DateTime<Local> {
+        let secs = dt.num_seconds_from_midnight() as u64;
+        let mut rounded = self.round_secs(secs);
+        if rounded >= SECONDS_PER_DAY {
+            rounded -= self.minutes * 60;
+        }
+
+        let time = NaiveTime::from_num_seconds_from_midnight(rounded as u32, 0);
+        Local
+            .from_local_datetime(&dt.naive_local().date().and_time(time))
+            .earliest()
+            .unwrap_or(*dt)
+    }
+
+    pub fn round_block(&self, block: &Block) -> Block {
+        Block {
+            from: self.round_dt(&block.from),
+            to: self.round_dt(&block.to),
+            ..block.clone()
+        }
+    }
+
+    /// Rounds a block that is about to be added to the day. With day scope
+    /// only its end moves, so that the day total becomes a multiple of the
+    /// increment. Ongoing blocks are rounded once they're closed.
+    pub fn round_new_block(&self, block: &Block, day: &Day) -> Block {
+        if self.scope == RoundingScope::Block {
+            return self.round_block(block);
+        }
+        if block.is_ongoing() {
+            return block.clone();
+        }
+
+        let others = day
+            .blocks
+            .iter()
+            .filter(|b| b.to <= block.from || b.from >= block.to)
+            .map(|b| b.duration())
+            .sum::<Duration>();
+        let total = others + block.duration();
+        let rounded = self.round_duration(&total);
+        let to = if rounded >= total {
+            block.to + chrono::Duration::from_std(rounded - total).unwrap()
+        } else {
+            block.to - chrono::Duration::from_std(total - rounded).unwrap()
+        };
+
+        // The block can't end on the next day
+        let end_of_day = Local
+            .from_local_datetime(&block.from.naive_local().date().and_hms(23, 59, 59))
+            .earliest()
+            .unwrap_or(block.to);
+
+        Block {
+            to: to.min(end_of_day).max(block.from),
+            ..block.clone()
+        }
+    }
+
+    pub fn day_duration(&self, day: &Day) -> Duration {
+        match self.scope {
+            RoundingScope::Block => day
+                .blocks
+                .iter()
+                .map(|b| self.round_duration(&b.duration()))
+                .sum::<Duration>(),
+            RoundingScope::Day => self.round_duration(&day.duration()),
+        }
+    }
+
+    pub fn month_duration(&self, month: &Month) -> Duration {
+        month
+            .days
+            .values()
+            .map(|d| self.day_duration(d))
+            .sum::<Duration>()
+    }
+
+    fn round_secs(&self, secs: u64) -> u64 {
+        let increment = self.minutes * 60;
+        let remainder = secs % increment;
+        let down = secs - remainder;
+
+        match self.mode {
+            _ if remainder == 0 => secs,
+            RoundingMode::Down => down,
+            RoundingMode::Up => down + increment,
+            RoundingMode::Nearest if remainder * 2 >= increment => down + increment,
+            RoundingMode::Nearest => down,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fmt;
+    use crate::parse;
+
+    fn minutes(m: u64) -> Duration {
+        Duration::from_secs(m * 60)
+    }
+
+    #[test]
+    fn test_round_duration() {
+        let mut rounding = Rounding::new(15);
+        assert_eq!(minutes(15), rounding.round_duration(&minutes(8)));
+        assert_eq!(minutes(0), rounding.round_duration(&minutes(7)));
+        assert_eq!(minutes(30), rounding.round_duration(&minutes(30)));
+
+        rounding.mode = RoundingMode::Up;
+        assert_eq!(minutes(15), rounding.round_duration(&minutes(1)));
+        assert_eq!(minutes(15), rounding.round_duration(&minutes(15)));
+
+        rounding.mode = RoundingMode::Down;
+        assert_eq!(minutes(0), rounding.round_duration(&minutes(14)));
+    }
+
+    #[test]
+    fn test_round_block() {
+        let date = NaiveDate::from_ymd(2022, 2, 12);
+        let rounding = Rounding::new(6);
+        let block = parse::parse_block(&date, "08:02-11:58");
+
+        assert_eq!(
+            parse::parse_block(&date, "08:00-12:00"),
+            rounding.round_block(&block)
+        );
+    }
+
+    #[test]
+    fn test_round_dt_stays_within_day() {
+        let date = NaiveDate::from_ymd(2022, 2, 12);
+        let mut rounding = Rounding::new(15);
+        rounding.mode = RoundingMode::Up;
+        let block = parse::parse_block(&date, "23:50-23:50");
+
+        assert_eq!(
+            "23:45",
+            fmt::format_block_date(&rounding.round_block(&block).to)
+        );
+    }
+
+    #[test]
+    fn test_day_duration() {
+        let day = parse::parse_day("12.02.22  08:00-08:08  09:00-09:08  Total: 00:16");
+        let mut rounding = Rounding::new(15);
+
+        assert_eq!(minutes(30), rounding.day_duration(&day));
+
+        rounding.scope = RoundingScope::Day;
+        assert_eq!(minutes(15), rounding.day_duration(&day));
+    }
+
+    #[test]
+    fn test_round_new_block() {
+        let day = parse::parse_day("12.02.22  08:00-08:08  Total: 00:08");
+        let block = parse::parse_block(&day.date, "09:00-09:08");
+        let mut rounding = Rounding::new(15);
+
+        assert_eq!(
+            parse::parse_block(&day.date, "09:00-09:15"),
+            rounding.round_new_block(&block, &day)
+        );
+
+        rounding.scope = RoundingScope::Day;
+        assert_eq!(
+            parse::parse_block(&day.date, "09:00-09:07"),
+            rounding.round_new_block(&block, &day)
+        );
+
+        let ongoing = parse::parse_block(&day.date, "09:00-09:00");
+        assert_eq!(ongoing, rounding.round_new_block(&ongoing, &day));
+    }
+
+    #[test]
+    fn test_round_new_block_stays_within_day() {
+        let day = parse::parse_day("12.02.22  08:00-08:10  Total: 00:10");
+        let block = parse::parse_block(&day.date, "23:50-23:58");
+        let mut rounding = Rounding::new(15);
+        rounding.mode = RoundingMode::Up;
+        rounding.scope = RoundingScope::Day;
+
+        assert_eq!(
+            parse::parse_block(&day.date, "23:50-23:59:59"),
+            rounding.round_new_block(&block, &day)
+        );
+    }
+
+    #[test]
+    fn test_increments() {
+        assert!(Rounding::try_new(6).is_ok());
+        assert!(Rounding::try_new(30).is_err());
+        assert!(Rounding::try_new(0).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::prelude::*;
+use regex::Regex;
+use regex::RegexBuilder;
+use std::collections::HashSet;
+
+use crate::day::Day;
+use crate::month::Month;
+
+/// Matches day comments, block notes and project tags. Plain queries match
+/// case-insensitively anywhere, regex queries as written.
+pub struct Search {
+    pattern: Regex,
+    dates: Option<HashSet<NaiveDate>>,
+}
+
+impl Search {
+    pub fn new(query: &str, regex: bool) -> Result<Self, regex::Error> {
+        let pattern = match regex {
+            true => Regex::new(query)?,
+            false => RegexBuilder::new(&regex::escape(query))
+                .case_insensitive(true)
+                .build()?,
+        };
+
+        Ok(Self {
+            pattern,
+            dates: None,
+        })
+    }
+
+    /// Only matches days of the given dates.
+    pub fn restrict(&mut self, dates: HashSet<NaiveDate>) {
+        self.dates = Some(dates);
+    }
+
+    pub fn matches(&self, day: &Day) -> bool {
+        if let Some(dates) = &self.dates {
+            if !dates.contains(&day.date) {
+                return false;
+            }
+        }
+
+        let comment = day.comment.iter();
+        let blocks = day
+            .blocks
+            .iter()
+            .flat_map(|b| b.note.iter().chain(b.project.iter()));
+
+        comment
+            .chain(blocks)
+            .any(|text| self.pattern.is_match(text))
+    }
+
+    /// Matching days of all months, sorted by date.
+    pub fn find(&self, months: &[Month]) -> Vec<Day> {
+        let mut days = months
+            .iter()
+            .flat_map(|m| m.sorted_days())
+            .filter(|d| self.matches(d))
+            .collect::<Vec<_>>();
+
+        days.sort();
+        days
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn months() -> Vec<Month> {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-12:00@acme "Standup"   Total: 04:00
+            02.02.22   08:00-12:00   Total: 04:00   sick
+            03.02.22   08:00-12:00@initech   Total: 04:00   standup moved
+
+            Total: 12:00
+        "#;
+
+        vec![Month::from_brf(contents, 2022, 2)]
+    }
+
+    fn dates(days: Vec<Day>) -> Vec<u32> {
+        days.iter().map(|d| d.date.day()).collect()
+    }
+
+    #[test]
+    fn test_search() {
+        let search = Search::new("standup", false).unwrap();
+        assert_eq!(vec![1, 3], dates(search.find(&months())));
+
+        let search = Search::new("ACME", false).unwrap();
+        assert_eq!(vec![1], dates(search.find(&months())));
+    }
+
+    #[test]
+    fn test_search_regex() {
+        let search = Search::new("^s(ick|tandup)", true).unwrap();
+        assert_eq!(vec![2, 3], dates(search.find(&months())));
+
+        assert!(Search::new("(", true).is_err());
+        assert!(Search::new("(", false).is_ok());
+    }
+
+    #[test]
+    fn test_search_range() {
+        let mut search = Search::new("standup", false).unwrap();
+        search.restrict(
+            NaiveDate::from_ymd(2022, 2, 2)
+                .iter_days()
+                .take_while(|d| d.month() == 2)
+                .collect(),
+        );
+        assert_eq!(vec![3], dates(search.find(&months())));
+
+        let mut search = Search::new("standup", false).unwrap();
+        search.restrict(HashSet::from([
+            NaiveDate::from_ymd(2022, 2, 1),
+            NaiveDate::from_ymd(2022, 2, 4),
+        ]));
+        assert_eq!(vec![1], dates(search.find(&months())));
+    }
+}
@@ -0,0 +1,399 @@
+use chrono::prelude::*;
+use punch::card::PunchCard;
+use punch::day::Day;
+use punch::fmt;
+use punch::fmt::OutputMode;
+use punch::infer;
+use punch::month::Month;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::Context;
+use rustyline::Editor;
+use rustyline::Helper;
+use std::path::Path;
+
+use crate::clock;
+
+pub const HISTORY_FILE_NAME: &str = ".punch_history";
+pub const PROMPT_DATE_FORMAT: &str = "%a %d.%m.%y";
+pub const COMPLETION_DATE_FORMAT: &str = "%d.%m.%Y";
+pub const COMMANDS: [&str; 8] = [
+    "day", "comment", "show", "undo", "write", "help", "quit", "quit!",
+];
+
+const HELP: &str = r#"830-12 now ...     add blocks to the selected day
+-r 12-13 ...       remove blocks from the selected day
+day 14 | 14.2      select another day
+comment [text]     set or clear the comment of the selected day
+show               show the month
+undo               revert the last change
+write              write the month to the BRF file
+quit, quit!        quit, quit without writing"#;
+
+/// Line-oriented shell for punching many corrections at once. Changes are
+/// kept in memory until `write`.
+pub struct Shell {
+    card: PunchCard,
+    month: Month,
+    date: NaiveDate,
+    undo_stack: Vec<Month>,
+    unsaved: bool,
+    dry_run: bool,
+    quit: bool,
+}
+
+impl Shell {
+    pub fn new(card: PunchCard, month: Month, date: &NaiveDate, dry_run: bool) -> Self {
+        let mut shell = Self {
+            card,
+            month,
+            date: *date,
+            undo_stack: vec![],
+            unsaved: false,
+            dry_run,
+            quit: false,
+        };
+        shell.card.select_date(date);
+        shell
+    }
+
+    pub fn run(&mut self) {
+        let history_path = Path::new(self.card.hours_dir_path()).join(HISTORY_FILE_NAME);
+        let mut editor = Editor::<DateCompleter>::new();
+        editor.set_helper(Some(DateCompleter {
+            month: self.month.month as u32,
+            year: self.month.year as i32,
+        }));
+        let _ = editor.load_history(&history_path);
+
+        while !self.quit {
+            let prompt = format!(
+                "{}> ",
+                self.card.locale.format(&self.date, PROMPT_DATE_FORMAT)
+            );
+            match editor.readline(&prompt) {
+                Ok(line) => {
+                    editor.add_history_entry(line.as_str());
+                    let output = self.eval(&line);
+                    if !output.is_empty() {
+                        println!("{}", output);
+                    }
+
+                    if let Some(helper) = editor.helper_mut() {
+                        helper.month = self.month.month as u32;
+                        helper.year = self.month.year as i32;
+                    }
+                }
+                Err(ReadlineError::Interrupted) => continue,
+                // No more input to answer a prompt, e.g. when piped
+                Err(ReadlineError::Eof) => {
+                    if self.unsaved {
+                        println!("Discarded unsaved changes");
+                    }
+                    self.quit = true;
+                }
+                Err(e) => panic!("Could not read line: {}", e),
+            }
+        }
+
+        let _ = editor.save_history(&history_path);
+    }
+
+    /// Evaluates a single line and returns the output to print.
+    pub fn eval(&mut self, line: &str) -> String {
+        let line = line.trim();
+        let (command, rest) = match line.split_once(char::is_whitespace) {
+            Some((command, rest)) => (command, rest.trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            "" => "".to_string(),
+            "help" => HELP.to_string(),
+            "show" => self.show(),
+            "day" => self.select_day(rest),
+            "comment" => {
+                self.modify(|day, _| match rest {
+                    "" => day.clear_comment(),
+                    comment => day.add_comment(comment),
+                });
+                self.show_day()
+            }
+            "undo" => match self.undo_stack.pop() {
+                Some(month) => {
+                    self.month = month;
+                    self.unsaved = true;
+                    self.show_day()
+                }
+                None => "Nothing to undo".to_string(),
+            },
+            "write" => {
+                clock::write_month(&mut self.month, &self.card, self.dry_run);
+                self.unsaved = false;
+                let path = self
+                    .card
+                    .brf_file_path(self.month.year as i32, self.month.month as u32);
+                match self.dry_run {
+                    true => format!("Dry run, not writing {}", path),
+                    false => format!("Wrote {}", path),
+                }
+            }
+            "quit" | "exit" if self.unsaved => "Unsaved changes, use write or quit!".to_string(),
+            "quit" | "exit" | "quit!" => {
+                self.quit = true;
+                "".to_string()
+            }
+            _ => self.punch_blocks(line),
+        }
+    }
+
+    fn punch_blocks(&mut self, line: &str) -> String {
+        let mut remove = false;
+        let mut block_strs = vec![];
+        for arg in line.split_whitespace() {
+            match arg {
+                "-r" | "--remove" => remove = true,
+                _ => block_strs.push(arg),
+            }
+        }
+
+        // Blocks are inferred one after another, but only applied if all are
+        // valid
+        let mut changed = self.day();
+        for block_str in block_strs {
+            let block = match clock::try_infer_block(&self.card, block_str, &changed) {
+                Ok(block) => block,
+                Err(e) => return e,
+            };
+            if remove {
+                changed.remove_block(&block);
+            } else {
+                changed.add_block(&block);
+            }
+        }
+
+        self.modify(|day, _| *day = changed);
+        self.show_day()
+    }
+
+    fn select_day(&mut self, input: &str) -> String {
+        let date = match input {
+            "" | "today" => Local::now().naive_local().date(),
+            "yesterday" => Local::now().naive_local().date().pred(),
+            _ => match infer::try_infer_date(input, &self.date) {
+                Ok(date) => date,
+                Err(e) => return e,
+            },
+        };
+
+        if date.month() != self.month.month as u32 || date.year() != self.month.year as i32 {
+            if self.unsaved {
+                return "Unsaved changes, write before switching months".to_string();
+            }
+
+            self.month = clock::read_month(&self.card, date.year(), date.month());
+            self.undo_stack.clear();
+        }
+
+        self.date = date;
+        self.card.select_date(&date);
+        self.show_day()
+    }
+
+    /// Applies a change to the selected day, remembering the previous state
+    /// for `undo`.
+    fn modify<F: FnOnce(&mut Day, &PunchCard)>(&mut self, change: F) {
+        self.undo_stack.push(self.month.clone());
+        self.month.add_day(&self.date);
+        change(self.month.find_day_by_date(&self.date).unwrap(), &self.card);
+        self.card.modify_date(&self.date);
+        self.unsaved = true;
+    }
+
+    fn show(&self) -> String {
+        fmt::format_month(&self.month, &self.card, OutputMode::Term)
+    }
+
+    /// The selected day, empty if it isn't in the month yet.
+    fn day(&self) -> Day {
+        let mut month = self.month.clone();
+        month.add_day(&self.date);
+        month.find_day_by_date(&self.date).unwrap().clone()
+    }
+
+    fn show_day(&self) -> String {
+        let day = self.day();
+
+        let mut parts = vec![self.card.locale.format_date(&day.date)];
+        parts.extend(
+            day.blocks
+                .iter()
+                .map(|b| fmt::format_block(b, OutputMode::Term, self.card.config.precision())),
+        );
+        parts.push(format!(
+            "Total: {}",
+            fmt::format_term_duration(&day.live_duration(&Local::now()), &self.card)
+        ));
+        if let Some(c) = &day.comment {
+            parts.push(c.clone());
+        }
+
+        parts.join(fmt::SPACER)
+    }
+}
+
+/// Completes command names and the dates of the current month after `day`.
+pub struct DateCompleter {
+    month: u32,
+    year: i32,
+}
+
+impl DateCompleter {
+    fn candidates(&self, line: &str) -> (usize, Vec<String>) {
+        match line.split_once(' ') {
+            Some(("day", prefix)) => {
+                let mut date = NaiveDate::from_ymd(self.year, self.month, 1);
+                let mut dates = vec![];
+                while date.month() == self.month {
+                    dates.push(date.format(COMPLETION_DATE_FORMAT).to_string());
+                    date = date.succ();
+                }
+
+                let prefix = prefix.trim_start();
+                let start = line.len() - prefix.len();
+                (
+                    start,
+                    dates
+                        .into_iter()
+                        .filter(|d| d.starts_with(prefix))
+                        .collect(),
+                )
+            }
+            Some(_) => (0, vec![]),
+            None => (
+                0,
+                COMMANDS
+                    .iter()
+                    .filter(|c| c.starts_with(line))
+                    .map(|c| c.to_string())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Completer for DateCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(&line[..pos]))
+    }
+}
+
+impl Hinter for DateCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for DateCompleter {}
+
+impl Validator for DateCompleter {}
+
+impl Helper for DateCompleter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use punch::config::Config;
+
+    fn card() -> PunchCard {
+        let dir = std::env::temp_dir().join(format!("punch-shell-{}", std::process::id()));
+        PunchCard::with_config(dir.to_str().unwrap(), Config::default()).unwrap()
+    }
+
+    fn shell() -> Shell {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-12:00   Total: 04:00
+
+            Total: 04:00
+        "#;
+        let month = Month::from_brf(contents, 2022, 2);
+        Shell::new(card(), month, &NaiveDate::from_ymd(2022, 2, 1), true)
+    }
+
+    #[test]
+    fn test_punching_and_undo() {
+        let mut shell = shell();
+        assert_eq!(
+            "Tue   01.02.22   08:00-12:00   13:00-17:00   Total: 08:00",
+            shell.eval("13-17")
+        );
+        assert_eq!(
+            "Tue   01.02.22   08:00-12:00   13:00-15:00   Total: 06:00",
+            shell.eval("-r 15-17")
+        );
+        assert_eq!(
+            "Tue   01.02.22   08:00-12:00   13:00-17:00   Total: 08:00",
+            shell.eval("undo")
+        );
+    }
+
+    #[test]
+    fn test_invalid_blocks() {
+        let mut shell = shell();
+        assert_eq!("Invalid block 'xyz'", shell.eval("13-17 xyz"));
+        assert_eq!(
+            "Tue   01.02.22   08:00-12:00   Total: 04:00",
+            shell.eval("show").lines().nth(2).unwrap()
+        );
+        assert!(!shell.unsaved);
+    }
+
+    #[test]
+    fn test_day_and_comment() {
+        let mut shell = shell();
+        shell.eval("day 14");
+        assert_eq!(
+            "Mon   14.02.22   Total: 00:00   sick",
+            shell.eval("comment sick")
+        );
+        assert_eq!("Mon   14.02.22   Total: 00:00", shell.eval("comment"));
+        assert_eq!("Invalid date '30'", shell.eval("day 30"));
+    }
+
+    #[test]
+    fn test_quit_with_unsaved_changes() {
+        let mut shell = shell();
+        shell.eval("13-17");
+        assert_eq!("Unsaved changes, use write or quit!", shell.eval("quit"));
+        assert!(!shell.quit);
+
+        assert!(shell.eval("write").starts_with("Dry run, not writing "));
+        shell.eval("quit");
+        assert!(shell.quit);
+    }
+
+    #[test]
+    fn test_complete_dates() {
+        let completer = DateCompleter {
+            month: 2,
+            year: 2022,
+        };
+
+        assert_eq!(
+            (4, vec!["28.02.2022".to_string()]),
+            completer.candidates("day 28")
+        );
+        assert_eq!((4, vec![]), completer.candidates("day 30"));
+        assert_eq!((0, vec!["show".to_string()]), completer.candidates("sh"));
+    }
+}
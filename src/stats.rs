@@ -0,0 +1,263 @@
+use chrono::prelude::*;
+use std::time::Duration;
+
+use crate::day::Day;
+use crate::fmt;
+use crate::fmt::DurationFormat;
+use crate::fmt::Precision;
+use crate::locale::Locale;
+use crate::month;
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const HISTOGRAM_WIDTH: usize = 30;
+const HISTOGRAM_MARK: &str = "#";
+
+/// Upper bounds of the block length buckets, the last bucket is open.
+const BUCKETS: [Duration; 4] = [
+    Duration::from_secs(30 * MINUTE),
+    Duration::from_secs(HOUR),
+    Duration::from_secs(2 * HOUR),
+    Duration::from_secs(4 * HOUR),
+];
+const BUCKET_LABELS: [&str; 5] = ["< 0:30", "< 1:00", "< 2:00", "< 4:00", ">= 4:00"];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub days_worked: usize,
+    pub average_start: Option<NaiveTime>,
+    pub average_end: Option<NaiveTime>,
+    /// Average day length from Monday to Sunday.
+    pub average_by_weekday: [Option<Duration>; 7],
+    pub longest_day: Option<(NaiveDate, Duration)>,
+    pub shortest_day: Option<(NaiveDate, Duration)>,
+    /// Number of blocks per bucket of `BUCKETS`.
+    pub block_lengths: [usize; 5],
+    pub longest_streak: usize,
+}
+
+impl Stats {
+    /// Computes the stats of the days with finished blocks. Ongoing blocks
+    /// are ignored.
+    pub fn from_days(days: &[Day]) -> Self {
+        let mut worked = days
+            .iter()
+            .filter(|d| d.duration() > Duration::ZERO)
+            .collect::<Vec<_>>();
+        worked.sort();
+
+        let mut stats = Self {
+            days_worked: worked.len(),
+            average_start: average_time(worked.iter().filter_map(|d| {
+                d.blocks
+                    .iter()
+                    .filter(|b| !b.is_ongoing())
+                    .map(|b| b.from)
+                    .min()
+            })),
+            average_end: average_time(worked.iter().filter_map(|d| {
+                d.blocks
+                    .iter()
+                    .filter(|b| !b.is_ongoing())
+                    .map(|b| b.to)
+                    .max()
+            })),
+            average_by_weekday: [None; 7],
+            longest_day: worked
+                .iter()
+                .map(|d| (d.date, d.duration()))
+                .max_by_key(|(_, duration)| *duration),
+            shortest_day: worked
+                .iter()
+                .map(|d| (d.date, d.duration()))
+                .min_by_key(|(_, duration)| *duration),
+            block_lengths: [0; 5],
+            longest_streak: longest_streak(&worked),
+        };
+
+        for (index, weekday) in stats.average_by_weekday.iter_mut().enumerate() {
+            let durations = worked
+                .iter()
+                .filter(|d| d.date.weekday().num_days_from_monday() as usize == index)
+                .map(|d| d.duration())
+                .collect::<Vec<_>>();
+
+            if !durations.is_empty() {
+                *weekday = Some(durations.iter().sum::<Duration>() / durations.len() as u32);
+            }
+        }
+
+        for block in worked.iter().flat_map(|d| d.blocks.iter()) {
+            if block.is_ongoing() {
+                continue;
+            }
+
+            let bucket = BUCKETS
+                .iter()
+                .position(|bound| block.duration() < *bound)
+                .unwrap_or(BUCKETS.len());
+            stats.block_lengths[bucket] += 1;
+        }
+
+        stats
+    }
+}
+
+fn average_time<I: Iterator<Item = DateTime<Local>>>(times: I) -> Option<NaiveTime> {
+    let secs = times
+        .map(|t| t.num_seconds_from_midnight())
+        .collect::<Vec<_>>();
+
+    if secs.is_empty() {
+        return None;
+    }
+
+    let average = secs.iter().map(|s| *s as u64).sum::<u64>() / secs.len() as u64;
+    NaiveTime::from_num_seconds_from_midnight_opt(average as u32, 0)
+}
+
+/// Most consecutive days worked. Weekends without work don't interrupt a
+/// streak.
+fn longest_streak(sorted_days: &[&Day]) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for day in sorted_days {
+        let continues = previous.map_or(false, |p| {
+            let mut next = p.succ();
+            while !month::is_workday(&next) && next < day.date {
+                next = next.succ();
+            }
+            next == day.date
+        });
+
+        current = if continues { current + 1 } else { 1 };
+        longest = longest.max(current);
+        previous = Some(day.date);
+    }
+
+    longest
+}
+
+pub fn format_stats(stats: &Stats, locale: &Locale, format: DurationFormat) -> String {
+    let duration = |d: &Duration| fmt::format_duration_as(d, Precision::Minutes, format);
+    let time = |t: &Option<NaiveTime>| {
+        t.map(|t| t.format(fmt::BLOCK_FORMAT).to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let day = |d: &Option<(NaiveDate, Duration)>| match d {
+        Some((date, d)) => format!("{} ({})", duration(d), locale.format_date(date)),
+        None => "-".to_string(),
+    };
+
+    let mut lines = vec![
+        format!("Days worked:     {}", stats.days_worked),
+        format!("Longest streak:  {}", stats.longest_streak),
+        format!("Average start:   {}", time(&stats.average_start)),
+        format!("Average end:     {}", time(&stats.average_end)),
+        format!("Longest day:     {}", day(&stats.longest_day)),
+        format!("Shortest day:    {}", day(&stats.shortest_day)),
+        "".to_string(),
+        "Average day length:".to_string(),
+    ];
+
+    let mut weekday = Weekday::Mon;
+    for average in stats.average_by_weekday.iter() {
+        let average = average
+            .map(|a| duration(&a))
+            .unwrap_or_else(|| "-".to_string());
+        lines.push(format!(
+            "{}{}{}{}",
+            fmt::SPACER,
+            locale.weekday(weekday),
+            fmt::SPACER,
+            average
+        ));
+        weekday = weekday.succ();
+    }
+
+    lines.push("".to_string());
+    lines.push("Block lengths:".to_string());
+    let max = stats
+        .block_lengths
+        .iter()
+        .max()
+        .copied()
+        .unwrap_or(0)
+        .max(1);
+    for (label, count) in BUCKET_LABELS.iter().zip(stats.block_lengths.iter()) {
+        let bar = HISTOGRAM_MARK.repeat(count * HISTOGRAM_WIDTH / max);
+        let line = format!("{}{:>7}  {:>4}  {}", fmt::SPACER, label, count, bar);
+        lines.push(line.trim_end().to_string());
+    }
+
+    lines.join(fmt::NEWLINE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::month::Month;
+
+    fn days() -> Vec<Day> {
+        let contents = r#"
+            February 2022
+
+            03.02.22   08:00-12:00   12:30-17:30   Total: 09:00
+            04.02.22   09:00-09:20   10:00-12:00   Total: 02:20
+            07.02.22   10:00-16:00   Total: 06:00
+            09.02.22   08:00-10:00   11:00-11:00   Total: 02:00
+
+            Total: 19:20
+        "#;
+
+        Month::from_brf(contents, 2022, 2).sorted_days()
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::from_days(&days());
+
+        assert_eq!(4, stats.days_worked);
+        assert_eq!(3, stats.longest_streak);
+        assert_eq!(Some(NaiveTime::from_hms(8, 45, 0)), stats.average_start);
+        assert_eq!(Some(NaiveTime::from_hms(13, 52, 30)), stats.average_end);
+        assert_eq!(
+            Some((
+                NaiveDate::from_ymd(2022, 2, 3),
+                Duration::from_secs(9 * HOUR)
+            )),
+            stats.longest_day
+        );
+        assert_eq!(
+            Some((
+                NaiveDate::from_ymd(2022, 2, 9),
+                Duration::from_secs(2 * HOUR)
+            )),
+            stats.shortest_day
+        );
+        assert_eq!(
+            Some(Duration::from_secs(6 * HOUR)),
+            stats.average_by_weekday[0]
+        );
+        assert_eq!(None, stats.average_by_weekday[1]);
+        assert_eq!([1, 0, 0, 2, 3], stats.block_lengths);
+
+        let formatted = format_stats(&stats, &Locale::default(), DurationFormat::Decimal(2));
+        assert!(formatted.contains("Longest day:     9.00 (Thu   03.02.22)"));
+        assert!(formatted.contains("Mon   6.00"));
+    }
+
+    #[test]
+    fn test_empty_stats() {
+        let stats = Stats::from_days(&[]);
+
+        assert_eq!(0, stats.longest_streak);
+        assert_eq!(None, stats.average_start);
+        assert!(
+            format_stats(&stats, &Locale::default(), DurationFormat::Clock)
+                .contains("Average start:   -")
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::card::PunchCard;
+use crate::fmt::format_month;
+use crate::fmt::OutputMode;
+use crate::month::Month;
+use crate::parse;
+use crate::parse::ParseError;
+
+pub const BRF_EXTENSION: &str = "txt";
+
+/// Why BRF files of a card couldn't be read or written.
+#[derive(Debug)]
+pub enum StoreError {
+    Config(String),
+    UnknownCard(String),
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, source: ParseError },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Config(message) => write!(f, "Invalid config: {}", message),
+            StoreError::UnknownCard(card) => write!(f, "Unknown card {}", card),
+            StoreError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            StoreError::Parse { path, source } => {
+                write!(f, "Could not parse BRF {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl error::Error for StoreError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            StoreError::Io { source, .. } => Some(source),
+            StoreError::Parse { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> StoreError + '_ {
+    move |source| StoreError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Infers year and month from BRF file names like `2022-2.txt` or
+/// `2022-02.txt`.
+pub fn parse_brf_file_name(path: &Path) -> Option<(i32, u32)> {
+    if path.extension()? != BRF_EXTENSION {
+        return None;
+    }
+
+    let stem = path.file_stem()?.to_str()?;
+    let (year, month) = stem.split_once('-')?;
+    let year = year.parse::<i32>().ok()?;
+    let month = month.parse::<u32>().ok()?;
+
+    if (1..=12).contains(&month) {
+        Some((year, month))
+    } else {
+        None
+    }
+}
+
+/// Reads the BRF file of the given month, creating it if it doesn't exist
+/// yet.
+pub fn read_month(card: &PunchCard, year: i32, month_number: u32) -> Result<Month, StoreError> {
+    let dir = card.card_dir_path();
+    fs::create_dir_all(&dir).map_err(io_error(Path::new(&dir)))?;
+
+    let path = PathBuf::from(card.brf_file_path(year, month_number));
+    let mut file = OpenOptions::new()
+        .read(true)
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(io_error(&path))?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(io_error(&path))?;
+
+    parse::try_parse_month(&contents, year as u16, month_number as u8)
+        .map_err(|source| StoreError::Parse { path, source })
+}
+
+/// Reads all valid BRF files of the selected card, sorted by month. Invalid
+/// files are skipped.
+pub fn read_all_months(card: &PunchCard) -> Result<Vec<Month>, StoreError> {
+    let dir = card.card_dir_path();
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(io_error(Path::new(&dir))(e)),
+    };
+
+    let mut months = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter_map(|path| {
+            let (year, month_number) = parse_brf_file_name(&path)?;
+            let contents = fs::read_to_string(&path).ok()?;
+            parse::try_parse_month(&contents, year as u16, month_number as u8).ok()
+        })
+        .collect::<Vec<_>>();
+
+    months.sort_by_key(|m| (m.year, m.month));
+    Ok(months)
+}
+
+/// Cleans up the month and writes it to its BRF file, which recalculates all
+/// totals.
+pub fn write_month(month: &mut Month, card: &PunchCard) -> Result<(), StoreError> {
+    month.cleanup();
+    let path = PathBuf::from(card.brf_file_path(month.year as i32, month.month as u32));
+
+    fs::write(&path, format_month(month, card, OutputMode::File)).map_err(io_error(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn card(name: &str) -> PunchCard {
+        let dir = std::env::temp_dir().join(format!("punch-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        PunchCard::with_config(dir.to_str().unwrap(), Config::default()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_brf_file_name() {
+        assert_eq!(
+            Some((2022, 2)),
+            parse_brf_file_name(Path::new("a/2022-2.txt"))
+        );
+        assert_eq!(
+            Some((2022, 11)),
+            parse_brf_file_name(Path::new("2022-11.txt"))
+        );
+        assert_eq!(
+            Some((2022, 2)),
+            parse_brf_file_name(Path::new("2022-02.txt"))
+        );
+        assert_eq!(None, parse_brf_file_name(Path::new("2022-13.txt")));
+        assert_eq!(None, parse_brf_file_name(Path::new("notes.txt")));
+        assert_eq!(None, parse_brf_file_name(Path::new("2022-2.md")));
+    }
+
+    #[test]
+    fn test_read_write_month() {
+        let card = card("month");
+        let mut month = read_month(&card, 2022, 2).unwrap();
+        assert!(month.days.is_empty());
+
+        month = parse::parse_month(
+            "February 2022\n\n01.02.22   08:00-12:00   Total: 01:00\n",
+            2022,
+            2,
+        );
+        write_month(&mut month, &card).unwrap();
+
+        let months = read_all_months(&card).unwrap();
+        assert_eq!(1, months.len());
+        assert_eq!(month, months[0]);
+        assert!(fs::read_to_string(card.brf_file_path(2022, 2))
+            .unwrap()
+            .contains("Total: 04:00"));
+
+        fs::remove_dir_all(card.hours_dir_path()).unwrap();
+    }
+
+    #[test]
+    fn test_store_errors() {
+        let mut card = card("errors");
+        assert!(matches!(
+            card.try_select_card("work"),
+            Err(StoreError::UnknownCard(_))
+        ));
+        assert!(read_all_months(&card).unwrap().is_empty());
+
+        fs::create_dir_all(card.hours_dir_path()).unwrap();
+        fs::write(
+            card.brf_file_path(2022, 3),
+            "March 2022\n\n01.03.22   8-12\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            read_month(&card, 2022, 3),
+            Err(StoreError::Parse { .. })
+        ));
+        assert!(read_all_months(&card).unwrap().is_empty());
+
+        assert_eq!(
+            "Invalid config: Unknown precision hours",
+            PunchCard::with_config(card.hours_dir_path(), Config::parse("precision = hours"))
+                .err()
+                .unwrap()
+                .to_string()
+        );
+
+        fs::remove_dir_all(card.hours_dir_path()).unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+use colored::*;
+use std::env::var;
+
+use crate::config::Config;
+
+pub const THEME_PREFIX: &str = "theme.";
+const NO_THEME_COLOR: &str = "none";
+const DEFAULT_SELECTED: Rgb = Rgb(201, 169, 250);
+const DEFAULT_MODIFIED: Rgb = Rgb(255, 146, 209);
+const GRAY_SATURATION: u8 = 48;
+
+/// The 16 ANSI colours with their usual xterm values, used to find the
+/// closest one on terminals without 256 colours.
+const ANSI_COLORS: [(Color, Rgb); 16] = [
+    (Color::Black, Rgb(0, 0, 0)),
+    (Color::Red, Rgb(205, 0, 0)),
+    (Color::Green, Rgb(0, 205, 0)),
+    (Color::Yellow, Rgb(205, 205, 0)),
+    (Color::Blue, Rgb(0, 0, 238)),
+    (Color::Magenta, Rgb(205, 0, 205)),
+    (Color::Cyan, Rgb(0, 205, 205)),
+    (Color::White, Rgb(229, 229, 229)),
+    (Color::BrightBlack, Rgb(127, 127, 127)),
+    (Color::BrightRed, Rgb(255, 0, 0)),
+    (Color::BrightGreen, Rgb(0, 255, 0)),
+    (Color::BrightYellow, Rgb(255, 255, 0)),
+    (Color::BrightBlue, Rgb(92, 92, 255)),
+    (Color::BrightMagenta, Rgb(255, 0, 255)),
+    (Color::BrightCyan, Rgb(0, 255, 255)),
+    (Color::BrightWhite, Rgb(255, 255, 255)),
+];
+
+/// Colours the terminal can show, detected from `COLORTERM` and `TERM`.
+/// `Plain` disables colours regardless of the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    Plain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// Row colours, configured like `theme.weekend = #808080` or
+/// `theme.selected = none`. Selected and modified rows are coloured by
+/// default, the others only if configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub selected: Option<Rgb>,
+    pub modified: Option<Rgb>,
+    pub weekend: Option<Rgb>,
+    pub holiday: Option<Rgb>,
+    pub ongoing: Option<Rgb>,
+    pub palette: Palette,
+}
+
+impl Theme {
+    pub fn from_config(config: &Config) -> Self {
+        Self::try_from_config(config).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_config(config: &Config) -> Result<Self, String> {
+        let color = |key: &str, default: Option<Rgb>| match config
+            .get(&format!("{}{}", THEME_PREFIX, key))
+        {
+            None => Ok(default),
+            Some(NO_THEME_COLOR) => Ok(None),
+            Some(c) => parse_rgb(c)
+                .map(Some)
+                .ok_or_else(|| format!("Invalid {} color {}", key, c)),
+        };
+
+        Ok(Self {
+            selected: color("selected", Some(DEFAULT_SELECTED))?,
+            modified: color("modified", Some(DEFAULT_MODIFIED))?,
+            weekend: color("weekend", None)?,
+            holiday: color("holiday", None)?,
+            ongoing: color("ongoing", None)?,
+            palette: Palette::detect(
+                var("COLORTERM").ok().as_deref(),
+                var("TERM").ok().as_deref(),
+            ),
+        })
+    }
+
+    /// Colours the text with the closest colour of the palette.
+    pub fn paint(&self, text: String, color: Option<Rgb>) -> String {
+        let Rgb(r, g, b) = match color {
+            Some(c) => c,
+            None => return text,
+        };
+
+        match self.palette {
+            Palette::TrueColor => text.truecolor(r, g, b).to_string(),
+            Palette::Ansi256 if control::SHOULD_COLORIZE.should_colorize() => {
+                format!("\x1b[38;5;{}m{}\x1b[0m", ansi256(Rgb(r, g, b)), text)
+            }
+            Palette::Ansi256 => text,
+            Palette::Ansi16 => text.color(ansi16(Rgb(r, g, b))).to_string(),
+            Palette::Plain => text,
+        }
+    }
+}
+
+impl Palette {
+    pub fn detect(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        match (colorterm, term) {
+            (Some("truecolor" | "24bit"), _) => Palette::TrueColor,
+            (_, Some(t)) if t.contains("256color") => Palette::Ansi256,
+            _ => Palette::Ansi16,
+        }
+    }
+}
+
+/// Parses `#rrggbb`.
+fn parse_rgb(color: &str) -> Option<Rgb> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Index of the closest colour in the 6x6x6 cube of the 256 colours.
+fn ansi256(Rgb(r, g, b): Rgb) -> u8 {
+    let level = |c: u8| ((c as u16 * 5 + 127) / 255) as u8;
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// Closest of the 16 colours. Saturated colours are only matched against the
+/// coloured ones, so a highlight doesn't end up plain white.
+fn ansi16(color: Rgb) -> Color {
+    let Rgb(r, g, b) = color;
+    let gray = r.max(g).max(b) - r.min(g).min(b) < GRAY_SATURATION;
+    let distance = |Rgb(r, g, b): Rgb| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, color.0) + d(g, color.1) + d(b, color.2)
+    };
+
+    ANSI_COLORS
+        .iter()
+        .filter(|(_, Rgb(r, g, b))| gray || !(r == g && g == b))
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|(c, _)| *c)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_palette() {
+        assert_eq!(
+            Palette::TrueColor,
+            Palette::detect(Some("truecolor"), Some("xterm-256color"))
+        );
+        assert_eq!(
+            Palette::Ansi256,
+            Palette::detect(None, Some("xterm-256color"))
+        );
+        assert_eq!(Palette::Ansi16, Palette::detect(None, Some("xterm")));
+        assert_eq!(Palette::Ansi16, Palette::detect(None, None));
+    }
+
+    #[test]
+    fn test_fallback_colors() {
+        assert_eq!(16, ansi256(Rgb(0, 0, 0)));
+        assert_eq!(231, ansi256(Rgb(255, 255, 255)));
+        assert_eq!(218, ansi256(DEFAULT_MODIFIED));
+        assert_eq!(Color::BrightMagenta, ansi16(DEFAULT_MODIFIED));
+        assert_eq!(Color::BrightBlue, ansi16(DEFAULT_SELECTED));
+        assert_eq!(Color::Red, ansi16(Rgb(190, 10, 10)));
+        assert_eq!(Color::BrightBlack, ansi16(Rgb(128, 128, 128)));
+    }
+
+    #[test]
+    fn test_theme_from_config() {
+        let config = Config::parse("theme.weekend = #808080\ntheme.selected = none");
+        let theme = Theme::from_config(&config);
+
+        assert_eq!(None, theme.selected);
+        assert_eq!(Some(DEFAULT_MODIFIED), theme.modified);
+        assert_eq!(Some(Rgb(128, 128, 128)), theme.weekend);
+        assert_eq!(None, theme.holiday);
+
+        assert_eq!(None, parse_rgb("808080"));
+        assert_eq!(None, parse_rgb("#80808"));
+        assert_eq!(None, parse_rgb("#80808g"));
+        assert_eq!(
+            Err("Invalid holiday color red".to_string()),
+            Theme::try_from_config(&Config::parse("theme.holiday = red"))
+        );
+    }
+}
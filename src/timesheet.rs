@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::compliance;
+use crate::config::Config;
+use crate::day::Day;
+use crate::fmt;
+use crate::fmt::DurationFormat;
+use crate::fmt::OutputMode;
+use crate::fmt::Precision;
+use crate::locale::Locale;
+use crate::month;
+use crate::month::Month;
+
+/// Name of the report in config keys like `duration_format.report`.
+pub const REPORT_OUTPUT: &str = "report";
+const STYLE: &str = r#"
+@page { size: A4; margin: 15mm; }
+body { font-family: sans-serif; font-size: 10pt; color: #000; }
+h1 { font-size: 16pt; margin: 0 0 4mm 0; }
+table { width: 100%; border-collapse: collapse; }
+th, td { border-bottom: 1px solid #999; padding: 1mm 2mm; text-align: left; }
+td.total, th.total { text-align: right; white-space: nowrap; }
+tr.weekend td { color: #777; }
+.meta td, .summary td { border: none; padding: 0.5mm 2mm 0.5mm 0; }
+.summary { width: auto; margin-top: 5mm; }
+.signatures { display: flex; justify-content: space-between; margin-top: 20mm; }
+.signature { width: 45%; border-top: 1px solid #000; padding-top: 1mm; }
+"#;
+
+/// Renders a self-contained, printable HTML timesheet for the given month,
+/// including totals, the overtime balance and signature lines.
+pub fn format_timesheet(month: &Month, config: &Config) -> String {
+    let locale = Locale::from_config(config);
+    let format = config.duration_format(REPORT_OUTPUT);
+    let duration = |d: &Duration| fmt::format_duration_as(d, Precision::Minutes, format);
+    let rows = month
+        .full_sorted_days()
+        .iter()
+        .map(|d| format_row(d, &locale, format, config.break_deduction()))
+        .collect::<Vec<_>>()
+        .join(fmt::NEWLINE);
+
+    let (worked, deduction) = match config.break_deduction() {
+        true => {
+            let deducted = compliance::month_missing_break(month);
+            (
+                month.duration() - deducted,
+                format!(
+                    "{}<tr><td>Break deduction:</td><td>{}</td></tr>",
+                    fmt::NEWLINE,
+                    duration(&deducted)
+                ),
+            )
+        }
+        false => (month.duration(), "".to_string()),
+    };
+    let holidays = config.holidays().into_iter().collect::<HashSet<_>>();
+    let target = month.target_duration(&config.daily_target(), &holidays);
+    let rounded = match config.rounding() {
+        Some(r) => format!(
+            "{}<tr><td>Rounded:</td><td>{}</td></tr>",
+            fmt::NEWLINE,
+            duration(&r.month_duration(month))
+        ),
+        None => "".to_string(),
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Timesheet {title}</title>
+<style>{style}</style>
+</head>
+<body>
+<h1>Timesheet</h1>
+<table class="meta">
+<tr><td>Name:</td><td>{name}</td></tr>
+<tr><td>Period:</td><td>{title}</td></tr>
+</table>
+<table class="days">
+<tr><th>Date</th><th>Blocks</th><th class="total">Total</th><th>Comment</th></tr>
+{rows}
+</table>
+<table class="summary">
+<tr><td>Worked:</td><td>{worked}</td></tr>{deduction}{rounded}
+<tr><td>Target:</td><td>{target}</td></tr>
+<tr><td>Overtime balance:</td><td>{balance}</td></tr>
+</table>
+<div class="signatures">
+<div class="signature">Date, signature employee</div>
+<div class="signature">Date, signature manager</div>
+</div>
+</body>
+</html>
+"#,
+        title = escape(&locale.month_title(month)),
+        style = STYLE,
+        name = escape(config.name().unwrap_or_default()),
+        rows = rows,
+        worked = duration(&worked),
+        deduction = deduction,
+        rounded = rounded,
+        target = duration(&target),
+        balance = fmt::format_balance(&worked, &target, format),
+    )
+}
+
+/// Day totals have missing breaks deducted if `deduct_breaks` is set.
+fn format_row(day: &Day, locale: &Locale, format: DurationFormat, deduct_breaks: bool) -> String {
+    // Column alignment of the terminal date format doesn't apply in HTML
+    let date = locale
+        .format_date(&day.date)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let class = if month::is_workday(&day.date) {
+        "workday"
+    } else {
+        "weekend"
+    };
+
+    let blocks = day
+        .blocks
+        .iter()
+        .map(|b| fmt::format_block(b, OutputMode::File, Precision::Minutes))
+        .collect::<Vec<_>>()
+        .join(fmt::SPACER);
+
+    let total = if day.blocks.is_empty() {
+        "".to_string()
+    } else {
+        let deducted = match deduct_breaks {
+            true => compliance::missing_break(day),
+            false => Duration::ZERO,
+        };
+        fmt::format_duration_as(&(day.duration() - deducted), Precision::Minutes, format)
+    };
+
+    format!(
+        r#"<tr class="{}"><td>{}</td><td>{}</td><td class="total">{}</td><td>{}</td></tr>"#,
+        class,
+        escape(&date),
+        escape(&blocks),
+        total,
+        escape(day.comment.as_deref().unwrap_or_default()).replace('\n', "<br>")
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_row() {
+        let day =
+            Day::from_brf("  01.02.22    08:00-12:00    13:00-17:30   Total: 08:30 <b>ugs</b>");
+
+        assert_eq!(
+            r#"<tr class="workday"><td>Tue 01.02.22</td><td>08:00-12:00   13:00-17:30</td><td class="total">08:30</td><td>&lt;b&gt;ugs&lt;/b&gt;</td></tr>"#,
+            format_row(&day, &Locale::default(), DurationFormat::Clock, false)
+        );
+    }
+
+    #[test]
+    fn test_format_timesheet() {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-18:00   Total: 10:00
+
+            Total: 10:00
+        "#;
+        let month = Month::from_brf(contents, 2022, 2);
+        let config = Config::parse("name = Jane Doe\ndaily_target = 08:00\nrounding = 15");
+        let sheet = format_timesheet(&month, &config);
+
+        assert!(sheet.contains("<td>Jane Doe</td>"));
+        assert!(sheet.contains("<td>February 2022</td>"));
+        assert!(sheet.contains("<tr><td>Worked:</td><td>10:00</td></tr>"));
+        assert!(sheet.contains("<tr><td>Rounded:</td><td>10:00</td></tr>"));
+        assert!(sheet.contains("<tr><td>Target:</td><td>160:00</td></tr>"));
+        assert!(sheet.contains("<tr><td>Overtime balance:</td><td>-150:00</td></tr>"));
+
+        let config = Config::parse("daily_target = 08:00\nholidays = 14.02.2022");
+        let sheet = format_timesheet(&month, &config);
+        assert!(sheet.contains("<tr><td>Target:</td><td>152:00</td></tr>"));
+    }
+
+    #[test]
+    fn test_break_deduction() {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-18:00   Total: 10:00
+
+            Total: 10:00
+        "#;
+        let month = Month::from_brf(contents, 2022, 2);
+        let config = Config::parse("break_deduction = on");
+        let sheet = format_timesheet(&month, &config);
+
+        assert!(sheet.contains("<td class=\"total\">09:15</td>"));
+        assert!(sheet.contains("<tr><td>Worked:</td><td>09:15</td></tr>"));
+        assert!(sheet.contains("<tr><td>Break deduction:</td><td>00:45</td></tr>"));
+    }
+}
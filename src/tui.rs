@@ -0,0 +1,493 @@
+use chrono::prelude::*;
+use colored::*;
+use crossterm::cursor;
+use crossterm::event;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use crossterm::execute;
+use crossterm::queue;
+use crossterm::style::Print;
+use crossterm::terminal;
+use punch::block::Block;
+use punch::card::PunchCard;
+use punch::day::Day;
+use punch::fmt;
+use punch::fmt::OutputMode;
+use punch::infer;
+use punch::month::Month;
+use std::io::stdout;
+use std::io::Write;
+use std::panic;
+
+use crate::clock;
+
+const HELP: &str =
+    "j/k day  h/l block  a add  b break  x remove  e end  c comment  [/] month  q quit";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Action {
+    AddBlock,
+    Break,
+    End,
+    Comment,
+}
+
+impl Action {
+    fn label(&self) -> &str {
+        match self {
+            Action::AddBlock => "Add block (e.g. 830-12): ",
+            Action::Break => "Break (e.g. 12-13): ",
+            Action::End => "New end (e.g. 1730): ",
+            Action::Comment => "Comment: ",
+        }
+    }
+}
+
+struct Prompt {
+    action: Action,
+    input: String,
+}
+
+/// Raw mode and the alternate screen, left again when dropped.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> crossterm::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+fn restore_terminal() {
+    let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Terminal UI for browsing and editing months. Every change is written
+/// right away through `clock::write_month`.
+pub struct Tui {
+    card: PunchCard,
+    month: Month,
+    cursor: usize,
+    block: Option<usize>,
+    prompt: Option<Prompt>,
+    message: Option<String>,
+    dry_run: bool,
+    quit: bool,
+}
+
+impl Tui {
+    pub fn new(card: PunchCard, month: Month, date: &NaiveDate, dry_run: bool) -> Self {
+        let cursor = if date.month() == month.month as u32 {
+            date.day0() as usize
+        } else {
+            0
+        };
+
+        Self {
+            card,
+            month,
+            cursor,
+            block: None,
+            prompt: None,
+            message: None,
+            dry_run,
+            quit: false,
+        }
+    }
+
+    pub fn run(&mut self) -> crossterm::Result<()> {
+        // Restore the terminal before a panic message is printed, so it's
+        // readable and the shell is usable afterwards
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            default_hook(info);
+        }));
+
+        let _terminal = RawTerminal::enter()?;
+        self.event_loop()
+    }
+
+    fn event_loop(&mut self) -> crossterm::Result<()> {
+        while !self.quit {
+            self.draw()?;
+            if let Event::Key(key) = event::read()? {
+                self.handle_key(key);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&self) -> crossterm::Result<()> {
+        let (_, height) = terminal::size()?;
+        let lines = self.lines();
+        let footer = lines.len() - 2;
+        let visible = (height as usize).saturating_sub(2).max(1);
+        let cursor_line = self.cursor_line();
+        let scroll = (cursor_line + 1).saturating_sub(visible).min(footer);
+
+        let mut out = stdout();
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        for line in lines[..footer].iter().skip(scroll).take(visible) {
+            queue!(out, Print(line), Print("\r\n"))?;
+        }
+
+        queue!(
+            out,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            Print(&lines[footer + 1])
+        )?;
+        out.flush()
+    }
+
+    fn days(&self) -> Vec<Day> {
+        self.month.full_sorted_days()
+    }
+
+    fn selected_day(&self) -> Day {
+        self.days().remove(self.cursor)
+    }
+
+    fn selected_block(&self) -> Option<Block> {
+        self.block
+            .and_then(|index| self.selected_day().blocks.get(index).cloned())
+    }
+
+    /// Index of the line the cursor is on, accounting for the title and the
+    /// blank lines between weeks.
+    fn cursor_line(&self) -> usize {
+        let weeks = self.days()[1..=self.cursor]
+            .iter()
+            .filter(|d| d.date.weekday() == Weekday::Mon)
+            .count();
+
+        2 + self.cursor + weeks
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!(
+                "{}   Total: {}",
+                self.card.locale.month_title(&self.month),
+                fmt::format_term_duration(&self.month.duration(), &self.card)
+            ),
+            "".to_string(),
+        ];
+
+        for (index, day) in self.days().iter().enumerate() {
+            if index > 0 && day.date.weekday() == Weekday::Mon {
+                lines.push("".to_string());
+            }
+            lines.push(self.format_row(index, day));
+        }
+
+        lines.push("".to_string());
+        lines.push(match (&self.prompt, &self.message) {
+            (Some(p), _) => format!("{}{}", p.action.label(), p.input),
+            (None, Some(m)) => m.clone(),
+            (None, None) => HELP.to_string(),
+        });
+
+        lines
+    }
+
+    fn format_row(&self, index: usize, day: &Day) -> String {
+        let is_cursor = index == self.cursor;
+        let date = self.card.locale.format_date(&day.date);
+
+        let blocks = day
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let block = fmt::format_block(b, OutputMode::Term, self.card.config.precision());
+                if is_cursor && self.block == Some(i) {
+                    block.reversed().to_string()
+                } else {
+                    block
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut columns = vec![date];
+        columns.extend(blocks);
+        columns.push(format!(
+            "Total: {}",
+            fmt::format_term_duration(&day.live_duration(&Local::now()), &self.card)
+        ));
+        if let Some(c) = &day.comment {
+            columns.push(c.clone());
+        }
+
+        let row = columns.join(fmt::SPACER);
+        let row = if self.card.was_modified(&day.date) {
+            self.card.theme.paint(row, self.card.theme.modified)
+        } else {
+            row
+        };
+
+        match (is_cursor, self.block) {
+            (true, None) => format!("> {}", row.reversed()),
+            (true, Some(_)) => format!("> {}", row),
+            _ => format!("  {}", row),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key);
+            return;
+        }
+
+        self.message = None;
+        let num_days = self.days().len();
+        let num_blocks = self.selected_day().blocks.len();
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.cursor = (self.cursor + 1).min(num_days - 1);
+                self.block = None;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.cursor = self.cursor.saturating_sub(1);
+                self.block = None;
+            }
+            KeyCode::Char('l') | KeyCode::Right if num_blocks > 0 => {
+                self.block = Some(match self.block {
+                    Some(i) => (i + 1).min(num_blocks - 1),
+                    None => 0,
+                });
+            }
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.block = match self.block {
+                    Some(0) | None => None,
+                    Some(i) => Some(i - 1),
+                };
+            }
+            KeyCode::Char('a') => self.open_prompt(Action::AddBlock, ""),
+            KeyCode::Char('b') => self.open_prompt(Action::Break, ""),
+            KeyCode::Char('e') if self.selected_block().is_some() => {
+                self.open_prompt(Action::End, "")
+            }
+            KeyCode::Char('c') => {
+                let comment = self.selected_day().comment.unwrap_or_default();
+                self.open_prompt(Action::Comment, &comment);
+            }
+            KeyCode::Char('x') => match self.selected_block() {
+                Some(block) => {
+                    self.modify(|day| day.remove_block(&block));
+                    self.block = None;
+                }
+                None => self.message = Some("Select a block with h/l first".to_string()),
+            },
+            KeyCode::Char('[') => self.change_month(infer::prev_month(self.month_year())),
+            KeyCode::Char(']') => self.change_month(infer::next_month(self.month_year())),
+            _ => (),
+        }
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let prompt = self.prompt.as_mut().unwrap();
+
+        match key.code {
+            KeyCode::Char(c) => prompt.input.push(c),
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                let prompt = self.prompt.take().unwrap();
+                self.submit(prompt.action, prompt.input.trim());
+            }
+            _ => (),
+        }
+    }
+
+    fn open_prompt(&mut self, action: Action, input: &str) {
+        self.prompt = Some(Prompt {
+            action,
+            input: input.to_string(),
+        });
+    }
+
+    fn submit(&mut self, action: Action, input: &str) {
+        if input.is_empty() && action != Action::Comment {
+            return;
+        }
+
+        if let Err(e) = self.try_submit(action, input) {
+            self.message = Some(e);
+        }
+    }
+
+    fn try_submit(&mut self, action: Action, input: &str) -> Result<(), String> {
+        let day = self.selected_day();
+        match action {
+            Action::AddBlock => {
+                let block = clock::try_infer_block(&self.card, input, &day)?;
+                self.modify(|d| d.add_block(&block));
+            }
+            Action::Break => {
+                let block = clock::try_infer_block(&self.card, input, &day)?;
+                self.modify(|d| d.remove_block(&block));
+            }
+            Action::End => {
+                if let Some(mut block) = self.selected_block() {
+                    let from = fmt::format_block_date(&block.from);
+                    let input = format!("{}{}{}", from, fmt::BLOCK_SEP, input);
+                    let end = clock::try_infer_block(&self.card, &input, &day)?.to;
+
+                    if end >= block.to {
+                        block.to = end;
+                        self.modify(|d| d.add_block(&block));
+                    } else {
+                        // Shrinking removes the rest of the block
+                        block.from = end;
+                        self.modify(|d| d.remove_block(&block));
+                    }
+                }
+            }
+            Action::Comment if input.is_empty() => self.modify(|d| d.clear_comment()),
+            Action::Comment => self.modify(|d| d.add_comment(input)),
+        }
+
+        Ok(())
+    }
+
+    /// Applies a change to the selected day and writes the month.
+    fn modify<F: FnOnce(&mut Day)>(&mut self, change: F) {
+        let date = self.selected_day().date;
+        self.month.add_day(&date);
+        change(self.month.find_day_by_date(&date).unwrap());
+
+        self.card.modify_date(&date);
+        clock::write_month(&mut self.month, &self.card, self.dry_run);
+
+        let num_blocks = self.selected_day().blocks.len();
+        self.block = self.block.filter(|i| *i < num_blocks);
+    }
+
+    fn month_year(&self) -> infer::MonthYear {
+        (self.month.month as u32, self.month.year as i32)
+    }
+
+    fn change_month(&mut self, (month_number, year): infer::MonthYear) {
+        self.month = clock::read_month(&self.card, year, month_number);
+        self.cursor = 0;
+        self.block = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use punch::config::Config;
+
+    fn card() -> PunchCard {
+        let dir = std::env::temp_dir().join(format!("punch-tui-{}", std::process::id()));
+        PunchCard::with_config(dir.to_str().unwrap(), Config::default()).unwrap()
+    }
+
+    fn tui() -> Tui {
+        let contents = r#"
+            February 2022
+
+            01.02.22   08:00-12:00   13:00-17:00   Total: 08:00
+
+            Total: 08:00
+        "#;
+        let month = Month::from_brf(contents, 2022, 2);
+        Tui::new(card(), month, &NaiveDate::from_ymd(2022, 2, 1), true)
+    }
+
+    fn press(tui: &mut Tui, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                _ => KeyCode::Char(c),
+            };
+            tui.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    fn blocks(tui: &Tui) -> Vec<String> {
+        tui.selected_day()
+            .blocks
+            .iter()
+            .map(|b| fmt::format_block(b, OutputMode::File, fmt::Precision::Minutes))
+            .collect()
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut tui = tui();
+        press(&mut tui, "jjk");
+        assert_eq!(NaiveDate::from_ymd(2022, 2, 2), tui.selected_day().date);
+
+        press(&mut tui, "kkkll");
+        assert_eq!(Some(1), tui.block);
+        assert_eq!(
+            "13:00",
+            fmt::format_block_date(&tui.selected_block().unwrap().from)
+        );
+    }
+
+    #[test]
+    fn test_editing_blocks() {
+        let mut tui = tui();
+        press(&mut tui, "a1730-18\n");
+        assert_eq!(
+            vec!["08:00-12:00", "13:00-17:00", "17:30-18:00"],
+            blocks(&tui)
+        );
+
+        press(&mut tui, "b1030-11\n");
+        assert_eq!(4, blocks(&tui).len());
+
+        press(&mut tui, "lllle19\n");
+        assert_eq!("17:30-19:00", blocks(&tui)[3]);
+
+        press(&mut tui, "e1830\n");
+        assert_eq!("17:30-18:30", blocks(&tui)[3]);
+
+        press(&mut tui, "x");
+        assert_eq!(3, blocks(&tui).len());
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let mut tui = tui();
+        press(&mut tui, "axyz\n");
+
+        assert_eq!(vec!["08:00-12:00", "13:00-17:00"], blocks(&tui));
+        assert_eq!(Some("Invalid block 'xyz'".to_string()), tui.message);
+
+        press(&mut tui, "j");
+        assert_eq!(None, tui.message);
+    }
+
+    #[test]
+    fn test_comment() {
+        let mut tui = tui();
+        press(&mut tui, "chi\n");
+        assert_eq!(Some("hi".to_string()), tui.selected_day().comment);
+    }
+}